@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::{fs, env};
 use std::io;
 use std::io::Write;
+use std::path::PathBuf;
 
 use crate::std_lib::*;
 use crate::types::*;
@@ -10,10 +11,15 @@ use crate::scanner::*;
 use crate::interpreter::*;
 use crate::parser::*;
 use crate::environment::*;
+use crate::diagnostics::*;
+use crate::errors::*;
+use crate::highlight::*;
 
 pub struct App { 
     pub interpreter : Interpreter,
     pub final_environment : Option<Environment>,
+    pub diagnostics : Diagnostics,
+    pub last_error : Option<LoxError>,
 }
 
 impl App {
@@ -21,17 +27,58 @@ impl App {
         Self { 
             interpreter : Interpreter::new(),
             final_environment : None,
+            diagnostics : Diagnostics::new(),
+            last_error : None,
         }        
     }
 
+    pub fn add_source(&mut self, name : &str, contents : &str) -> usize {
+        self.interpreter.sources.push((name.to_string(), contents.to_string()));
+        self.interpreter.sources.len()-1
+    }
+
+    //imports that aren't found next to the importing file are looked for in these, in the order added
+    pub fn add_search_path(&mut self, path : &str) {
+        self.interpreter.search_paths.push(PathBuf::from(path));
+    }
+
     pub fn play(&mut self) {
         let args: Vec<String> = env::args().collect();
+        if let Ok(paths) = env::var("LOX_PATH") {
+            for path in env::split_paths(&paths) {
+                self.interpreter.search_paths.push(path);
+            }
+        }
 
         if args.len() > 1 {
             if args[1].clone() == "repl" {
                 if let Err(e) = self.run_repl() {
-                    Self::error(e.0, &e.1);
+                    self.report_error(&e);
+                }
+            }
+            else if args[1].clone() == "check" {
+                if args.len() < 3 {
+                    panic!("Provide a lox file to check.");
+                }
+                if !self.check_file(args[2].clone()) {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            else if args[1].clone() == "tokens" {
+                if args.len() < 3 {
+                    panic!("Provide a lox file to tokenize.");
+                }
+                println!("{}", self.tokens_file(args[2].clone()));
+                return;
+            }
+            else if args[1].clone() == "highlight" {
+                if args.len() < 3 {
+                    panic!("Provide a lox file to highlight.");
                 }
+                let html = args.len() > 3 && args[3].clone() == "--html";
+                print!("{}", self.highlight_file(args[2].clone(), html));
+                return;
             }
             else {
                 self.run_from_file(args[1].clone());
@@ -64,11 +111,13 @@ impl App {
                 break;
             }
 
-            let mut scanner = Scanner::new(String::from(user_input));
-            let tokens = scanner.scan_tokens()?;
-
-            let mut parser = Parser::new(tokens.to_vec());
-            let statements = parser.parse()?;
+            let statements = match self.compile("repl", &user_input) {
+                Ok(v) => v,
+                Err(_) => {
+                    self.report_diagnostics(&self.diagnostics);
+                    continue;
+                }
+            };
             
             self.interpreter.interpret(statements)?;
             if self.interpreter.stdout != "" {
@@ -98,6 +147,80 @@ impl App {
         println!("[line {} ] error {}: {}", line, where_str, message);
     }
 
+    pub fn report_diagnostics(&self, diagnostics : &Diagnostics) {
+        for error in &diagnostics.errors {
+            self.report_error(error);
+        }
+        match diagnostics.len() {
+            0 => { },
+            1 => println!("found 1 error"),
+            n => println!("found {} errors", n)
+        }
+    }
+
+    pub fn report_error(&self, error : &LoxError) {
+        print!("{}", self.render_error(error));
+    }
+
+    pub fn render_error(&self, error : &LoxError) -> String {
+        let mut o = self.render_location(error);
+        o.push_str(&Self::render_traceback(error.stack()));
+        o
+    }
+
+    //innermost call first, each frame names the line it was called from
+    pub fn render_traceback(stack : &[CallFrame]) -> String {
+        if stack.is_empty() {
+            return String::new();
+        }
+
+        let mut o = String::from("traceback (most recent call first):\n");
+        for frame in stack.iter().rev() {
+            let name = match &frame.class {
+                Some(c) if *c != frame.function => format!("{}.{}", c, frame.function),
+                _ => frame.function.clone()
+            };
+            o.push_str(&format!("  at {} (called from line {})\n", name, frame.line));
+        }
+        o
+    }
+
+    //rustc style diagnostic, falls back to the line report when the token has no source position
+    fn render_location(&self, error : &LoxError) -> String {
+        let token = error.token();
+        let message = error.message();
+        let span = token.span;
+        let source = self.interpreter.sources.get(span.file);
+
+        if !span.is_known() || source.is_none() {
+            if token.type_ == TokenType::Eof || token.lexeme.is_empty() {
+                return format!("[line {} ] error : {}\n", token.line, message);
+            }
+            return format!("[line {} ] error  at '{}': {}\n", token.line, token.lexeme, message);
+        }
+
+        let (name, contents) = source.unwrap();
+        let line_text = contents.lines().nth((span.line-1).max(0) as usize).unwrap_or("");
+        let gutter = " ".repeat(span.line.to_string().len());
+
+        let line_len = line_text.chars().count();
+        let offset = std::cmp::min(span.column-1, line_len);
+        let width = token.lexeme.lines().next().unwrap_or("").chars().count();
+        let width = std::cmp::max(1, std::cmp::min(width, line_len.saturating_sub(offset)));
+
+        let padding : String = line_text.chars()
+            .take(offset)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let mut o = format!("error: {}\n", message);
+        o.push_str(&format!("{}--> {}:{}:{}\n", gutter, name, span.line, span.column));
+        o.push_str(&format!("{} |\n", gutter));
+        o.push_str(&format!("{} | {}\n", span.line, line_text));
+        o.push_str(&format!("{} | {}{}\n", gutter, padding, "^".repeat(width)));
+        o
+    }
+
     fn add_std_lib(&mut self) -> RuntimeError<()> {
         self.interpreter.insert_function(function_container!(DebugFunction));
         self.interpreter.insert_function(function_container!(LenFunction));
@@ -105,34 +228,131 @@ impl App {
         self.interpreter.insert_function(function_container!(RandomFunction));
         self.interpreter.insert_function(function_container!(HashFunction));
         self.interpreter.insert_function(function_container!(CollectFunction));
+        self.interpreter.insert_function(function_container!(HelpFunction));
         self.interpreter.insert_value("PI", 3.14159265359.into());
 
-        let mut scanner = Scanner::new(String::from(STD_LIB_SCRIPT));
-        let tokens = scanner.scan_tokens()?;
-        let mut parser = Parser::new(tokens.to_vec());
-        let statements = parser.parse()?;
+        let statements = self.compile("std", STD_LIB_SCRIPT)?;
         self.interpreter.interpret(statements)?;
 
+        //Error goes in last and from an empty scope, a class keeps a copy of the scope it's declared in so anywhere else it'd weigh down every class after it
+        let statements = self.compile("std", ERROR_SCRIPT)?;
+        let globals = std::mem::replace(&mut self.interpreter.environment, Environment::new());
+        let res = self.interpreter.interpret(statements);
+        let error = self.interpreter.environment.get(&self.interpreter, Token::new(TokenType::Identifier, "Error", None, 0));
+        self.interpreter.environment = globals;
+        res?;
+        if let Some(error) = error? {
+            self.interpreter.insert_value("Error", error);
+        }
+
+        self.interpreter.prelude = Some(self.interpreter.environment.clone());
+
         Ok(())
     }
 
+    //scans and parses a source, every syntax error found is kept in self.diagnostics
+    pub fn compile(&mut self, name : &str, source : &str) -> RuntimeError<Vec<Box<dyn Stmt>>> {
+        let file = self.add_source(name, source);
+        let mut scanner = Scanner::new_with_file(String::from(source), file);
+        let _ = scanner.scan_tokens();
+
+        let mut parser = Parser::new(scanner.tokens.clone());
+        let statements = parser.parse();
+
+        self.diagnostics = scanner.diagnostics;
+        self.diagnostics.extend(parser.diagnostics);
+        self.diagnostics.sort();
+
+        match self.diagnostics.first() {
+            Some(e) => Err(e),
+            None => statements
+        }
+    }
+
+    pub fn check(&mut self, name : &str, source : &str) -> Diagnostics {
+        let _ = self.compile(name, source);
+        self.diagnostics.clone()
+    }
+
+    fn read_file(path : &str) -> String {
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Can't read file from path -> {}", path))
+    }
+
+    pub fn check_file(&mut self, path : String) -> bool {
+        let contents = App::read_file(&path);
+
+        let diagnostics = self.check(&path, &contents);
+        self.report_diagnostics(&diagnostics);
+        !diagnostics.has_errors()
+    }
+
+    //scans with comments kept, errors are left in the output instead of stopping the scan
+    pub fn tokenize(&mut self, name : &str, source : &str) -> Scanner {
+        let file = self.add_source(name, source);
+        let mut scanner = Scanner::new_with_file(String::from(source), file);
+        scanner.keep_comments = true;
+        let _ = scanner.scan_tokens();
+        scanner
+    }
+
+    pub fn tokens_file(&mut self, path : String) -> String {
+        let contents = App::read_file(&path);
+
+        self.tokenize(&path, &contents).tokens_json()
+    }
+
+    pub fn highlight_file(&mut self, path : String, html : bool) -> String {
+        let contents = App::read_file(&path);
+
+        let scanner = self.tokenize(&path, &contents);
+        match html {
+            true => to_html(&path, &contents, &scanner.tokens),
+            false => to_ansi(&contents, &scanner.tokens)
+        }
+    }
+
     pub fn try_run(&mut self, source : &str) -> RuntimeError<()> {
-        self.add_std_lib()?;
+        self.try_run_named("script", source)
+    }
 
-        let mut scanner = Scanner::new(String::from(source));
-        let tokens = scanner.scan_tokens()?;
+    pub fn try_run_named(&mut self, name : &str, source : &str) -> RuntimeError<()> {
+        self.last_error = None;
+        let res = self.execute(name, source);
+        if let Err(e) = &res {
+            self.last_error = Some(e.clone());
+        }
+        res
+    }
+
+    fn execute(&mut self, name : &str, source : &str) -> RuntimeError<()> {
+        self.add_std_lib()?;
 
-        let mut parser = Parser::new(tokens.to_vec());
-        let statements = parser.parse()?;
+        let statements = self.compile(name, source)?;
         
         self.interpreter.interpret(statements)?;
         self.final_environment = Some(self.interpreter.environment.clone());
         Ok(())
     }
 
+    pub fn traceback(&self) -> Vec<CallFrame> {
+        match &self.last_error {
+            Some(e) => e.stack().clone(),
+            None => Vec::new()
+        }
+    }
+
     pub fn run(&mut self, source : &str) {
-        if let Err((token, msg)) = self.try_run(source) {
-            App::error(token, msg.as_str());
+        self.run_named("script", source);
+    }
+
+    pub fn run_named(&mut self, name : &str, source : &str) {
+        if let Err(e) = self.try_run_named(name, source) {
+            if self.diagnostics.has_errors() {
+                self.report_diagnostics(&self.diagnostics);
+            }
+            else {
+                self.report_error(&e);
+            }
         }
     }
 
@@ -140,7 +360,12 @@ impl App {
         let contents = fs::read_to_string(path.clone())
         .expect(format!("Can't read file from path -> {}", path.clone()).as_str());
 
-        self.run(&contents);
+        //the script's own imports resolve next to it
+        if let Ok(file) = fs::canonicalize(&path) {
+            self.interpreter.loading.push(file);
+        }
+        self.run_named(&path, &contents);
+        self.interpreter.loading.clear();
     }
 
     pub fn get_value_raw(&self, name : &str) -> Option<Literal> {
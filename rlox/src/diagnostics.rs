@@ -0,0 +1,48 @@
+use crate::tokens::*;
+use crate::errors::*;
+
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    pub errors : Vec<LoxError>
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            errors : Vec::new()
+        }
+    }
+
+    pub fn error(&mut self, token : Token, message : &str) {
+        self.errors.push(LoxError::syntax(token, message));
+    }
+
+    pub fn push(&mut self, error : LoxError) {
+        self.errors.push(error);
+    }
+
+    pub fn extend(&mut self, other : Diagnostics) {
+        self.errors.extend(other.errors);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    //order by position in the source so reports read top to bottom
+    pub fn sort(&mut self) {
+        self.errors.sort_by_key(|e| (e.span().file, e.token().line, e.span().column));
+    }
+
+    pub fn first(&self) -> Option<LoxError> {
+        self.errors.first().cloned()
+    }
+}
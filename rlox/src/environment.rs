@@ -1,10 +1,12 @@
 use std::collections::HashMap;
-use crate::{types::*, tokens::*};
+use crate::{types::*, tokens::*, errors::*};
 use crate::interpreter::Interpreter;
 
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub values : HashMap<String, Option<Literal>>,
+    //declared types of the variables that have one, every assignment to them is checked
+    pub types : HashMap<String, TypeAnnotation>,
     pub enclosing : Option<Box<Environment>>
 }
 
@@ -12,6 +14,7 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             values : HashMap::new(),
+            types : HashMap::new(),
             enclosing : None
         }
     }
@@ -19,9 +22,22 @@ impl Environment {
     pub fn new_with_enclosing(enclosing_ : Environment) -> Self {
         Self { 
             values : HashMap::new(),
+            types : HashMap::new(),
             enclosing : Some(Box::new(enclosing_))
         }
     }
+
+    //how many scopes are chained up to the globals, counting this one
+    pub fn depth(&self) -> usize {
+        match &self.enclosing {
+            Some(e) => e.depth() + 1,
+            None => 1
+        }
+    }
+
+    pub fn annotate(&mut self, name : String, annotation : TypeAnnotation) {
+        self.types.insert(name, annotation);
+    }
     
     pub fn define_reference(&mut self, i : &mut Interpreter, value : Option<Literal>) -> usize {
         if let Some(Literal::Instance(x)) = value.clone() {
@@ -31,8 +47,14 @@ impl Environment {
     }
 
     pub fn define(&mut self, i : &mut Interpreter, name : String, value : Option<Literal>) {
+        //redeclaring a name drops whatever type it had, a typed declaration sets it again afterwards
+        self.types.remove(&name);
         if let Some(Literal::Instance(x)) = value.clone() {
             if let Some(a) = x.address {
+                //a temporary can lose its slot to the collector before it's bound, slots are never reused so it takes it back
+                if let Some(slot @ None) = i.references.get_mut(a) {
+                    *slot = Some(Literal::Instance(x.clone()));
+                }
                 if i.references.get(a).is_some() {
                     self.values.insert(name.clone(), Some(Literal::Instance(x.clone())));
                 }
@@ -56,11 +78,11 @@ impl Environment {
                     Some(a) => {
                         match i.references.get(a) {
                             Some(v) => Ok(v.clone()),
-                            None => Err((name.clone(), format!("Could not Find Var")))
+                            None => Err(LoxError::undefined_variable(name.clone(), "Could not Find Var"))
                         }
                     }
                     None => {
-                        Err((name.clone(), format!("Could not Find Var")))
+                        Err(LoxError::undefined_variable(name.clone(), "Could not Find Var"))
                     }
                 }
             }
@@ -74,7 +96,7 @@ impl Environment {
                     v.get(i, name.clone())
                 },
                 None => {
-                    Err((name.clone(), format!("Could not Find Var")))
+                    Err(LoxError::undefined_variable(name.clone(), "Could not Find Var"))
                 }
             }
         }
@@ -82,6 +104,17 @@ impl Environment {
 
     pub fn assign(&mut self, i : &mut Interpreter, name : Token, value : Option<Literal>) -> RuntimeError<Option<Box<Environment>>> {
         if self.values.contains_key(&name.lexeme) {
+            if let Some(annotation) = self.types.get(&name.lexeme) {
+                if !annotation.accepts(&value) {
+                    return Err(LoxError::type_mismatch(name.clone(), &format!(
+                        "Cannot assign {} to '{}' of type {}.",
+                        Interpreter::type_name(&value),
+                        name.lexeme,
+                        annotation
+                    )));
+                }
+            }
+            
             if let Some(Literal::Instance(inst2)) = value.clone() {
                 i.references[inst2.address.unwrap()] = Some(Literal::Instance(inst2.clone()));
                 self.values.insert(name.lexeme, Some(Literal::Instance(inst2.clone())));
@@ -97,9 +130,9 @@ impl Environment {
                 self.enclosing.as_mut().unwrap().assign(i, name, value)
             }
             else {
-                Err((
+                Err(LoxError::undefined_variable(
                     name.clone(), 
-                    format!("Undefined Variable '{}'.", name.lexeme.clone())
+                    &format!("Undefined Variable '{}'.", name.lexeme.clone())
                 ))
             }
         }
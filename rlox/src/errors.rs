@@ -0,0 +1,179 @@
+use std::fmt;
+
+use crate::tokens::*;
+use crate::types::Literal;
+
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    pub function : String,
+    pub class : Option<String>,
+    pub line : i32,
+}
+
+impl CallFrame {
+    pub fn new(function : String, class : Option<String>, line : i32) -> Self {
+        Self {
+            function,
+            class,
+            line
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ErrorInfo {
+    pub token : Token,
+    pub message : String,
+    pub stack : Vec<CallFrame>,
+}
+
+impl ErrorInfo {
+    pub fn new(token : Token, message : &str) -> Self {
+        Self {
+            token,
+            message : message.to_string(),
+            stack : Vec::new()
+        }
+    }
+}
+
+//details are boxed so results stay small on the hot visitor paths
+#[derive(Clone, Debug)]
+pub enum LoxError {
+    Syntax(Box<ErrorInfo>),
+    UndefinedVariable(Box<ErrorInfo>),
+    TypeMismatch(Box<ErrorInfo>),
+    Arity(Box<ErrorInfo>),
+    IndexOutOfRange(Box<ErrorInfo>),
+    NotCallable(Box<ErrorInfo>),
+    UndefinedProperty(Box<ErrorInfo>),
+    NativeError(Box<ErrorInfo>),
+    NonExhaustiveMatch(Box<ErrorInfo>),
+    Import(Box<ErrorInfo>),
+    //a value raised by a throw statement, carried up to the nearest catch
+    Thrown(Box<ErrorInfo>, Box<Option<Literal>>),
+}
+
+impl LoxError {
+    pub fn syntax(token : Token, message : &str) -> Self {
+        LoxError::Syntax(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn undefined_variable(token : Token, message : &str) -> Self {
+        LoxError::UndefinedVariable(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn type_mismatch(token : Token, message : &str) -> Self {
+        LoxError::TypeMismatch(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn arity(token : Token, message : &str) -> Self {
+        LoxError::Arity(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn index_out_of_range(token : Token, message : &str) -> Self {
+        LoxError::IndexOutOfRange(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn not_callable(token : Token, message : &str) -> Self {
+        LoxError::NotCallable(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn undefined_property(token : Token, message : &str) -> Self {
+        LoxError::UndefinedProperty(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn native_error(token : Token, message : &str) -> Self {
+        LoxError::NativeError(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn non_exhaustive_match(token : Token, message : &str) -> Self {
+        LoxError::NonExhaustiveMatch(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn import(token : Token, message : &str) -> Self {
+        LoxError::Import(Box::new(ErrorInfo::new(token, message)))
+    }
+
+    pub fn thrown(token : Token, message : &str, value : Option<Literal>) -> Self {
+        LoxError::Thrown(Box::new(ErrorInfo::new(token, message)), Box::new(value))
+    }
+
+    pub fn info(&self) -> &ErrorInfo {
+        match self {
+            LoxError::Syntax(i) |
+            LoxError::UndefinedVariable(i) |
+            LoxError::TypeMismatch(i) |
+            LoxError::Arity(i) |
+            LoxError::IndexOutOfRange(i) |
+            LoxError::NotCallable(i) |
+            LoxError::UndefinedProperty(i) |
+            LoxError::NativeError(i) |
+            LoxError::NonExhaustiveMatch(i) |
+            LoxError::Import(i) |
+            LoxError::Thrown(i, _) => i
+        }
+    }
+
+    pub fn info_mut(&mut self) -> &mut ErrorInfo {
+        match self {
+            LoxError::Syntax(i) |
+            LoxError::UndefinedVariable(i) |
+            LoxError::TypeMismatch(i) |
+            LoxError::Arity(i) |
+            LoxError::IndexOutOfRange(i) |
+            LoxError::NotCallable(i) |
+            LoxError::UndefinedProperty(i) |
+            LoxError::NativeError(i) |
+            LoxError::NonExhaustiveMatch(i) |
+            LoxError::Import(i) |
+            LoxError::Thrown(i, _) => i
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LoxError::Syntax(_) => "Syntax",
+            LoxError::UndefinedVariable(_) => "UndefinedVariable",
+            LoxError::TypeMismatch(_) => "TypeMismatch",
+            LoxError::Arity(_) => "Arity",
+            LoxError::IndexOutOfRange(_) => "IndexOutOfRange",
+            LoxError::NotCallable(_) => "NotCallable",
+            LoxError::UndefinedProperty(_) => "UndefinedProperty",
+            LoxError::NativeError(_) => "NativeError",
+            LoxError::NonExhaustiveMatch(_) => "NonExhaustiveMatch",
+            LoxError::Import(_) => "Import",
+            LoxError::Thrown(_, _) => "Thrown",
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.info().token
+    }
+
+    pub fn message(&self) -> &str {
+        &self.info().message
+    }
+
+    pub fn span(&self) -> Span {
+        self.info().token.span
+    }
+
+    pub fn stack(&self) -> &Vec<CallFrame> {
+        &self.info().stack
+    }
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let token = self.token();
+        if token.type_ == TokenType::Eof || token.lexeme.is_empty() {
+            write!(f, "[line {} ] {} error: {}", token.line, self.kind(), self.message())
+        }
+        else {
+            write!(f, "[line {} ] {} error at '{}': {}", token.line, self.kind(), token.lexeme, self.message())
+        }
+    }
+}
+
+impl std::error::Error for LoxError { }
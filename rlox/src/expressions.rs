@@ -1,6 +1,7 @@
 use std::any::Any;
 use crate::types::*;
 use crate::tokens::*;
+use crate::statements::Pattern;
 
 #[derive(Clone, Debug)]
 pub struct Binary {
@@ -36,13 +37,16 @@ impl Grouping {
 pub struct Get {
     pub name : Token,
     pub object : Box<dyn Expr>,
+    //?. gives nil instead of an error when the object is nil
+    pub optional : bool
 }
 
 impl Get {
     pub fn new(name : Token, object : Box<dyn Expr>) -> Self {
         Self {
             name,
-            object
+            object,
+            optional : false
         }
     }
 }
@@ -139,6 +143,23 @@ impl Assign {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct AssignPattern {
+    pub keyword : Token,
+    pub pattern : Pattern,
+    pub value : Box<dyn Expr>
+}
+
+impl AssignPattern {
+    pub fn new(keyword : Token, pattern : Pattern, value : Box<dyn Expr>) -> Self {
+        Self {
+            keyword,
+            pattern,
+            value
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Logical {
     pub left : Box<dyn Expr>,
@@ -160,7 +181,9 @@ impl Logical {
 pub struct Call {
     pub callee : Box<dyn Expr>,
     pub paren : Token,
-    pub arguments : Vec<Box<dyn Expr>>
+    pub arguments : Vec<Box<dyn Expr>>,
+    //name: value arguments, always after the positional ones
+    pub named : Vec<(Token, Box<dyn Expr>)>
 }
 
 impl Call {
@@ -168,7 +191,24 @@ impl Call {
         Self {
             callee,
             paren, 
-            arguments
+            arguments,
+            named : Vec::new()
+        }
+    }
+}
+
+//...list inside a call's arguments, passes each item as its own argument
+#[derive(Clone, Debug)]
+pub struct Spread {
+    pub dots : Token,
+    pub value : Box<dyn Expr>
+}
+
+impl Spread {
+    pub fn new(dots : Token, value : Box<dyn Expr>) -> Self {
+        Self {
+            dots,
+            value
         }
     }
 }
@@ -204,6 +244,7 @@ pub struct IndexGet {
     pub keyword : Token,
     pub index : Box<dyn Expr>,
     pub object : Box<dyn Expr>,
+    pub optional : bool
 }
 
 impl IndexGet {
@@ -211,7 +252,22 @@ impl IndexGet {
         Self {
             keyword,
             index,
-            object
+            object,
+            optional : false
+        }
+    }
+}
+
+//a postfix chain with at least one `?.` or `?[` link, a nil found at one of them makes the whole chain nil
+#[derive(Clone, Debug)]
+pub struct OptionalChain {
+    pub expression : Box<dyn Expr>,
+}
+
+impl OptionalChain {
+    pub fn new(expression : Box<dyn Expr>) -> Self {
+        Self {
+            expression
         }
     }
 }
@@ -259,6 +315,78 @@ impl Ternary {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct MapLiteral {
+    pub brace : Token,
+    pub entries : Vec<(Box<dyn Expr>, Box<dyn Expr>)>
+}
+
+impl MapLiteral {
+    pub fn new(brace : Token, entries : Vec<(Box<dyn Expr>, Box<dyn Expr>)>) -> Self {
+        Self {
+            brace,
+            entries
+        }
+    }
+}
+
+//what a for in loop walks over, maps give their keys or [key, value] pairs and lists give [index, item] pairs
+#[derive(Clone, Debug)]
+pub struct Iterable {
+    pub keyword : Token,
+    pub value : Box<dyn Expr>,
+    pub pairs : bool
+}
+
+impl Iterable {
+    pub fn new(keyword : Token, value : Box<dyn Expr>, pairs : bool) -> Self {
+        Self {
+            keyword,
+            value,
+            pairs
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RangeExpr {
+    pub operator : Token,
+    pub start : Box<dyn Expr>,
+    pub end : Box<dyn Expr>,
+    pub step : Option<Box<dyn Expr>>
+}
+
+impl RangeExpr {
+    pub fn new(operator : Token, start : Box<dyn Expr>, end : Box<dyn Expr>, step : Option<Box<dyn Expr>>) -> Self {
+        Self {
+            operator,
+            start,
+            end,
+            step
+        }
+    }
+}
+
+//start:stop:step inside [], only ever used as the index of an IndexGet or IndexSet
+#[derive(Clone, Debug)]
+pub struct Slice {
+    pub colon : Token,
+    pub start : Option<Box<dyn Expr>>,
+    pub stop : Option<Box<dyn Expr>>,
+    pub step : Option<Box<dyn Expr>>
+}
+
+impl Slice {
+    pub fn new(colon : Token, start : Option<Box<dyn Expr>>, stop : Option<Box<dyn Expr>>, step : Option<Box<dyn Expr>>) -> Self {
+        Self {
+            colon,
+            start,
+            stop,
+            step
+        }
+    }
+}
+
 impl Expr for VarExpr {
     fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
         visitor.visit_var_expr(self)
@@ -357,6 +485,20 @@ impl Expr for Assign {
     }
 }
 
+impl Expr for AssignPattern { 
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_assign_pattern_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
 impl Expr for Call {
     fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
         visitor.visit_call_expr(self)
@@ -441,6 +583,20 @@ impl Expr for Index {
     }
 }
 
+impl Expr for OptionalChain {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_optional_chain_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
 impl Expr for IndexGet {
     fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
         visitor.visit_index_get_expr(self)
@@ -469,6 +625,62 @@ impl Expr for IndexSet {
     }
 }
 
+impl Expr for MapLiteral {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_map_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
+impl Expr for Iterable {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_iterable_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
+impl Expr for RangeExpr {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_range_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
+impl Expr for Slice {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_slice_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
 impl Expr for Ternary {
     fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
         visitor.visit_ternary_expr(self)
@@ -478,6 +690,20 @@ impl Expr for Ternary {
         self
     }
     
+    fn clone_dyn(&self) -> Box<dyn Expr> {
+        Box::new(self.clone()) 
+    }
+}
+
+impl Expr for Spread {
+    fn accept(&self, visitor : &mut Box<&mut dyn ExprVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_spread_expr(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
     fn clone_dyn(&self) -> Box<dyn Expr> {
         Box::new(self.clone()) 
     }
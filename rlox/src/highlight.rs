@@ -0,0 +1,139 @@
+use crate::tokens::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TokenClass {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Constant,
+    Comment,
+    Operator,
+    Punctuation,
+    Identifier,
+    Eof,
+}
+
+impl TokenClass {
+    pub fn of(token : &Token) -> Self {
+        match token.type_ {
+            //num, string and bool double as literal kinds, only the keywords carry no value
+            TokenType::Number | TokenType::String | TokenType::Bool if token.literal.is_none() => TokenClass::Type,
+            TokenType::String | TokenType::Interpolation => TokenClass::String,
+            TokenType::Number => TokenClass::Number,
+            TokenType::Bool | TokenType::True | TokenType::False | TokenType::Nil => TokenClass::Constant,
+            TokenType::Comment | TokenType::DocComment => TokenClass::Comment,
+            TokenType::Identifier => TokenClass::Identifier,
+            TokenType::Class | TokenType::Else | TokenType::ElseIf | TokenType::Fn |
+            TokenType::For | TokenType::If | TokenType::As | TokenType::Is | TokenType::In |
+            TokenType::Print | TokenType::PrintLn | TokenType::Return | TokenType::Super |
+            TokenType::This | TokenType::Var | TokenType::While | TokenType::Break |
+            TokenType::Continue | TokenType::Match | TokenType::Enum | TokenType::Interface |
+            TokenType::Impl | TokenType::Abstract | TokenType::Static | TokenType::Throw |
+            TokenType::Try | TokenType::Catch | TokenType::Finally | TokenType::Import => TokenClass::Keyword,
+            TokenType::LeftParen | TokenType::RightParen | TokenType::LeftBrace |
+            TokenType::RightBrace | TokenType::LeftBracket | TokenType::RightBracket |
+            TokenType::Comma | TokenType::Semicolon | TokenType::Dot => TokenClass::Punctuation,
+            TokenType::Eof => TokenClass::Eof,
+            _ => TokenClass::Operator
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenClass::Keyword => "keyword",
+            TokenClass::Type => "type",
+            TokenClass::String => "string",
+            TokenClass::Number => "number",
+            TokenClass::Constant => "constant",
+            TokenClass::Comment => "comment",
+            TokenClass::Operator => "operator",
+            TokenClass::Punctuation => "punctuation",
+            TokenClass::Identifier => "identifier",
+            TokenClass::Eof => "eof",
+        }
+    }
+
+    fn ansi(&self) -> Option<&'static str> {
+        match self {
+            TokenClass::Keyword => Some("35"),
+            TokenClass::Type => Some("36"),
+            TokenClass::String => Some("32"),
+            TokenClass::Number | TokenClass::Constant => Some("33"),
+            TokenClass::Comment => Some("90"),
+            TokenClass::Operator => Some("34"),
+            _ => None
+        }
+    }
+}
+
+pub fn json_string(s : &str) -> String {
+    let mut o = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => o.push_str("\\\""),
+            '\\' => o.push_str("\\\\"),
+            '\n' => o.push_str("\\n"),
+            '\r' => o.push_str("\\r"),
+            '\t' => o.push_str("\\t"),
+            c if (c as u32) < 0x20 => o.push_str(&format!("\\u{:04x}", c as u32)),
+            c => o.push(c)
+        }
+    }
+    o.push('"');
+    o
+}
+
+fn html_escape(s : &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+//walks the tokens in order, text between them (whitespace or anything the scanner rejected) is copied through untouched
+fn render(source : &str, tokens : &[Token], paint : &dyn Fn(TokenClass, &str) -> String, plain : &dyn Fn(&str) -> String) -> String {
+    let mut o = String::new();
+    let mut at = 0;
+
+    for token in tokens {
+        let class = TokenClass::of(token);
+        if class == TokenClass::Eof || token.span.start < at || token.span.end > source.len() {
+            continue;
+        }
+
+        o.push_str(&plain(&source[at..token.span.start]));
+        o.push_str(&paint(class, &source[token.span.start..token.span.end]));
+        at = token.span.end;
+    }
+
+    o.push_str(&plain(&source[at..]));
+    o
+}
+
+pub fn to_ansi(source : &str, tokens : &[Token]) -> String {
+    render(source, tokens, &|class, text| {
+        match class.ansi() {
+            Some(color) => format!("\x1b[{}m{}\x1b[0m", color, text),
+            None => text.to_string()
+        }
+    }, &|text| text.to_string())
+}
+
+pub fn to_html(title : &str, source : &str, tokens : &[Token]) -> String {
+    let body = render(source, tokens, &|class, text| {
+        format!("<span class=\"{}\">{}</span>", class.name(), html_escape(text))
+    }, &html_escape);
+
+    let mut o = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    o.push_str(&format!("<title>{}</title>\n", html_escape(title)));
+    o.push_str("<style>\n");
+    o.push_str("pre.lox { background: #1e1e1e; color: #d4d4d4; padding: 1em; }\n");
+    o.push_str(".keyword { color: #c586c0; }\n");
+    o.push_str(".type { color: #4ec9b0; }\n");
+    o.push_str(".string { color: #ce9178; }\n");
+    o.push_str(".number, .constant { color: #b5cea8; }\n");
+    o.push_str(".comment { color: #6a9955; }\n");
+    o.push_str(".operator { color: #569cd6; }\n");
+    o.push_str("</style>\n</head>\n<body>\n");
+    o.push_str(&format!("<pre class=\"lox\">{}</pre>\n", body));
+    o.push_str("</body>\n</html>\n");
+    o
+}
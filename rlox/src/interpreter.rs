@@ -1,17 +1,44 @@
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use crate::tokens::*;
 use crate::types::*;
+use crate::errors::*;
 use crate::environment::*;
 use crate::statements::*;
 use crate::expressions::*;
+use crate::scanner::Scanner;
+use crate::parser::Parser;
+
+//what picks the items out of a list or string, either a [a:b:c] slice or a range value
+enum Selection<'a> {
+    Slice(&'a Slice),
+    Range(LoxRange, &'a Token)
+}
 
 pub struct Interpreter {
    pub environment : Environment,
    pub stdout : String,
    pub time : Instant,
-   pub references : Vec<Option<Literal>>
+   pub references : Vec<Option<Literal>>,
+   pub frames : Vec<CallFrame>,
+   //class-level fields, one table per class declaration, found through LoxClass::statics
+   pub statics : Vec<HashMap<String, Option<Literal>>>,
+   //every file scanned so far with its contents, tokens point into it through their span
+   pub sources : Vec<(String, String)>,
+   //imported files by canonical path, each one runs once
+   pub modules : HashMap<PathBuf, LoxModule>,
+   //the files being run, innermost last, imports resolve next to the last one
+   pub loading : Vec<PathBuf>,
+   pub search_paths : Vec<PathBuf>,
+   //the globals a module starts with, set once the standard library is in
+   pub prelude : Option<Environment>,
+   //scopes of the files waiting on an import, the collector still has to see them
+   suspended : Vec<Environment>,
+   //set when a `?.` or `?[` link meets nil, the rest of its chain is skipped until the OptionalChain ends
+   short_circuit : bool
 }
 
 impl Interpreter {
@@ -21,9 +48,37 @@ impl Interpreter {
             time : Instant::now(),
             stdout : "".to_string(),
             references : Vec::new(),
+            frames : Vec::new(),
+            statics : Vec::new(),
+            sources : Vec::new(),
+            modules : HashMap::new(),
+            loading : Vec::new(),
+            search_paths : Vec::new(),
+            prelude : None,
+            suspended : Vec::new(),
+            short_circuit : false
         }
     }
 
+    pub fn push_frame(&mut self, frame : CallFrame) {
+        self.frames.push(frame);
+    }
+
+    //the innermost failing call records the full stack before the frames unwind
+    pub fn pop_frame(&mut self, res : RuntimeError<Option<Literal>>) -> RuntimeError<Option<Literal>> {
+        let res = match res {
+            Err(mut e) => {
+                if e.stack().is_empty() {
+                    e.info_mut().stack = self.frames.clone();
+                }
+                Err(e)
+            },
+            v => v
+        };
+        self.frames.pop();
+        res
+    }
+
     fn garbage_helper(references : &Vec<Option<Literal>>, found : &mut Vec<usize>, inst : &Option<Literal>) {
         if let Some(Literal::Instance(i)) = inst {
             if let Some(a) = i.address {
@@ -41,6 +96,11 @@ impl Interpreter {
                 Self::garbage_helper(references, found, &c[i]);
             }
         }
+        if let Some(Literal::Module(m)) = inst {
+            for v in m.exports.values.values() {
+                Self::garbage_helper(references, found, v);
+            }
+        }
     }
 
     pub fn get_ref_count(&self) -> usize {
@@ -48,17 +108,35 @@ impl Interpreter {
     }
 
     pub fn collect_garbage(&mut self) {
+        self.collect_garbage_keeping(&None);
+    }
+
+    //keep is a value on its way out of a block, nothing refers to it yet but it still has to survive
+    pub fn collect_garbage_keeping(&mut self, keep : &Option<Literal>) {
         let mut found : Vec<usize> = Vec::new();
-        let mut e = self.environment.clone();
-        loop {
-            for v in &e.values {
-                Self::garbage_helper(&self.references, &mut found, v.1);
-            }
+        Self::garbage_helper(&self.references, &mut found, keep);
+        for scope in std::iter::once(&self.environment).chain(self.suspended.iter()) {
+            let mut e = scope.clone();
+            loop {
+                for v in &e.values {
+                    Self::garbage_helper(&self.references, &mut found, v.1);
+                }
 
-            if e.enclosing.is_none() {
-                break;
+                if e.enclosing.is_none() {
+                    break;
+                }
+                e = *e.enclosing.unwrap();
+            }
+        }
+        for module in self.modules.values() {
+            for v in module.exports.values.values() {
+                Self::garbage_helper(&self.references, &mut found, v);
+            }
+        }
+        for fields in &self.statics {
+            for v in fields.values() {
+                Self::garbage_helper(&self.references, &mut found, v);
             }
-            e = *e.enclosing.unwrap();
         }
         for i in 0..self.references.len() {
             if found.iter().find(|&x| *x == i).is_none() { 
@@ -145,7 +223,7 @@ impl Interpreter {
                                         Ok(Some(Literal::Collection(x)))
                                     },
                                     _ => {
-                                        Err((name.clone(), "Operands must be Numbers Or Strings.".to_string()))
+                                        Err(LoxError::type_mismatch(name.clone(), "Operands must be Numbers Or Strings."))
                                     }
                                 }
                             },
@@ -203,7 +281,7 @@ impl Interpreter {
                                         Ok(Some(Literal::String(x)))
                                     }
                                     _ => {
-                                        Err((name.clone(), "Operands must be Numbers.".to_string()))
+                                        Err(LoxError::type_mismatch(name.clone(), "Operands must be Numbers."))
                                     }
                                 }
                             },
@@ -219,7 +297,7 @@ impl Interpreter {
                                         Ok(Some(Literal::StrongNumber(x / y)))
                                     }
                                     _ => {
-                                        Err((name.clone(), "Operands must be Numbers.".to_string()))
+                                        Err(LoxError::type_mismatch(name.clone(), "Operands must be Numbers."))
                                     }
                                 }
                             },
@@ -235,7 +313,7 @@ impl Interpreter {
                                         Ok(Some(Literal::StrongNumber(x * y)))
                                     }
                                     _ => {
-                                        Err((name.clone(), "Operands must be Numbers.".to_string()))
+                                        Err(LoxError::type_mismatch(name.clone(), "Operands must be Numbers."))
                                     }
                                 }
                             },
@@ -251,11 +329,11 @@ impl Interpreter {
                                         Ok(Some(Literal::StrongNumber(x.rem_euclid(y))))
                                     },
                                     _ => {
-                                        Err((name.clone(), "Operands must be Numbers.".to_string()))
+                                        Err(LoxError::type_mismatch(name.clone(), "Operands must be Numbers."))
                                     }
                                 }
                             },
-                            _ => Err((name.clone(), "Invalid assign".to_string()))
+                            _ => Err(LoxError::type_mismatch(name.clone(), "Invalid assign"))
                         }
                     }
                     None => {
@@ -264,21 +342,21 @@ impl Interpreter {
                                 match y {
                                     Literal::StrongNumber(y_val) => Ok(Some(Literal::StrongNumber(y_val))),
                                     Literal::Number(y_val) => Ok(Some(Literal::StrongNumber(y_val))),
-                                    _ => Err((name.clone(), "Invalid assign".to_string()))
+                                    _ => Err(LoxError::type_mismatch(name.clone(), "Invalid assign"))
                                 } 
                             },
                             (Some(Literal::StrongString(_)), Some(y)) => {
                                 match y {
                                     Literal::StrongString(y_val) => Ok(Some(Literal::StrongString(y_val))),
                                     Literal::String(y_val) => Ok(Some(Literal::StrongString(y_val))),
-                                    _ => Err((name.clone(), "Invalid assign".to_string()))
+                                    _ => Err(LoxError::type_mismatch(name.clone(), "Invalid assign"))
                                 } 
                             },
                             (Some(Literal::StrongBoolean(_)), Some(y)) => {
                                 match y {
                                     Literal::StrongBoolean(y_val) => Ok(Some(Literal::StrongBoolean(y_val))),
                                     Literal::Boolean(y_val) => Ok(Some(Literal::Boolean(y_val))),
-                                    _ => Err((name.clone(), "Invalid assign".to_string()))
+                                    _ => Err(LoxError::type_mismatch(name.clone(), "Invalid assign"))
                                 } 
                             },
                             _ => Ok(value.clone())
@@ -330,6 +408,35 @@ impl Interpreter {
                     true => format!("{}\n", n)
                 }
             },
+            Some(Literal::Enum(e)) => {
+                match new_line {
+                    false => format!("enum {}", e.name),
+                    true => format!("enum {}\n", e.name)
+                }
+            },
+            Some(Literal::Interface(i)) => {
+                match new_line {
+                    false => format!("interface {}", i.name),
+                    true => format!("interface {}\n", i.name)
+                }
+            },
+            Some(Literal::Module(m)) => {
+                match new_line {
+                    false => format!("module {}", m.name),
+                    true => format!("module {}\n", m.name)
+                }
+            },
+            Some(Literal::Variant(v)) => {
+                let mut o = format!("{}.{}", v.enum_name, v.name);
+                if !v.values.is_empty() {
+                    let values : Vec<String> = v.values.iter().map(|x| self.print_helper(x.clone(), false, tabs+1)).collect();
+                    o.push_str(&format!("({})", values.join(", ")));
+                }
+                if new_line {
+                    o.push('\n');
+                }
+                o
+            },
             Some(Literal::Function(f)) => {
                 match new_line {
                     false => format!("fn => {:#?}", f),
@@ -391,6 +498,30 @@ impl Interpreter {
                     }
                 }
             },
+            Some(Literal::Range(r)) => {
+                let mut o = format!("{}{}{}", r.start, if r.inclusive { "..=" } else { ".." }, r.end);
+                if r.step != 1.0 {
+                    o.push_str(&format!(" step {}", r.step));
+                }
+                if new_line {
+                    o.push('\n');
+                }
+                o
+            },
+            Some(Literal::Map(m)) => {
+                let mut o = String::from("{");
+                for (i, (k, v)) in m.entries().iter().enumerate() {
+                    if i != 0 {
+                        o.push_str(", ");
+                    }
+                    o.push_str(&format!("{}: {}", self.print_helper(Some(k.clone()), false, tabs+1), self.print_helper(v.clone(), false, tabs+1)));
+                }
+                o.push('}');
+                if new_line {
+                    o.push('\n');
+                }
+                o
+            },
             Some(n) => { 
                 match new_line {
                     false => format!("{:#?}", n),
@@ -424,7 +555,17 @@ impl Interpreter {
         expr.accept(&mut Box::new(self as &mut dyn ExprVisitor))
     }
     
-    fn is_equal(&self, a : Option<Literal>, b : Option<Literal>) -> Option<Literal> {
+    //an instance whose class defines __eq__ decides for itself, that method can fail so this can too
+    fn is_equal(&mut self, a : Option<Literal>, b : Option<Literal>, at : &Token) -> RuntimeError<Option<Literal>> {
+        if let Some(Literal::Instance(i)) = &a {
+            if let Some(v) = self.special_method(i, "__eq__", vec![b.clone()], at)? {
+                return Ok(Some(Literal::Boolean(matches!(self.is_truthy(v), Some(Literal::Boolean(true))))));
+            }
+        }
+        Ok(self.structural_equal(a, b))
+    }
+
+    fn structural_equal(&self, a : Option<Literal>, b : Option<Literal>) -> Option<Literal> {
         match (a, b) {
             (None, None) => {
                 Some(Literal::Boolean(true))
@@ -467,6 +608,11 @@ impl Interpreter {
                     (Literal::StrongString(a_tt), Literal::String(b_tt)) => {
                         Some(Literal::Boolean(a_tt == b_tt))
                     },
+                    (Literal::Variant(a_tt), Literal::Variant(b_tt)) => {
+                        let same = a_tt.enum_name == b_tt.enum_name && a_tt.name == b_tt.name && a_tt.values.iter().zip(b_tt.values.iter())
+                            .all(|(x, y)| matches!(self.structural_equal(x.clone(), y.clone()), Some(Literal::Boolean(true))));
+                        Some(Literal::Boolean(same))
+                    },
                     _ => { None }
                 }
                 
@@ -477,56 +623,610 @@ impl Interpreter {
         }
     }
    
+    //the negation of structural_equal, so every kind == compares, variants included, can be told apart with != too
     fn is_not_equal(&self, a : Option<Literal>, b : Option<Literal>) -> Option<Literal> {
-        match (a, b) {
-            (None, None) => {
-                Some(Literal::Boolean(false))
+        match self.structural_equal(a, b) {
+            Some(Literal::Boolean(equal)) => Some(Literal::Boolean(!equal)),
+            other => other
+        }
+    }
+
+    //the check behind `x is num`, key is one of num, string or bool
+    pub fn is_type(value : &Option<Literal>, key : &str) -> bool {
+        match value {
+            Some(Literal::Number(_)) | Some(Literal::StrongNumber(_)) => key == "num",
+            Some(Literal::String(_)) | Some(Literal::StrongString(_)) => key == "string",
+            Some(Literal::Boolean(_)) | Some(Literal::StrongBoolean(_)) => key == "bool",
+            _ => false
+        }
+    }
+
+    pub fn type_name(value : &Option<Literal>) -> String {
+        match value {
+            Some(Literal::Number(_)) | Some(Literal::StrongNumber(_)) => "num".to_string(),
+            Some(Literal::String(_)) | Some(Literal::StrongString(_)) => "string".to_string(),
+            Some(Literal::Boolean(_)) | Some(Literal::StrongBoolean(_)) => "bool".to_string(),
+            Some(Literal::Collection(_)) | Some(Literal::StrongCollection(_)) => "list".to_string(),
+            Some(Literal::Map(_)) => "map".to_string(),
+            Some(Literal::Range(_)) => "range".to_string(),
+            Some(Literal::Function(_)) => "function".to_string(),
+            Some(Literal::Class(c)) => format!("class {}", c.name),
+            Some(Literal::Enum(e)) => format!("enum {}", e.name),
+            Some(Literal::Variant(v)) => v.enum_name.clone(),
+            Some(Literal::Interface(i)) => format!("interface {}", i.name),
+            Some(Literal::Module(m)) => format!("module {}", m.name),
+            Some(Literal::Instance(i)) => format!("{} instance", i.class.name),
+            Some(_) => "value".to_string(),
+            None => "nil".to_string()
+        }
+    }
+
+    //variants count as instances of their enum and instances count as their interfaces, so either name works as a type and a match pattern too
+    pub fn is_instance_of(value : &Option<Literal>, class_name : &str) -> bool {
+        if let Some(Literal::Variant(v)) = value {
+            return v.enum_name == class_name;
+        }
+        if let Some(Literal::Instance(i)) = value {
+            let mut class = Some(&i.class);
+            while let Some(c) = class {
+                if c.name == class_name || c.interfaces.iter().any(|i| i.name == class_name) {
+                    return true;
+                }
+                class = c.super_class.as_ref();
+            }
+        }
+        false
+    }
+
+    //names bound by the pattern are pushed onto bindings, nothing is defined until the whole arm matches
+    //a pattern that doesn't fit is a miss, only errors from __eq__ come back as errors
+    fn match_pattern(&mut self, pattern : &Pattern, value : &Option<Literal>, at : &Token, bindings : &mut Vec<(String, Option<Literal>)>) -> RuntimeError<bool> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Literal(l) => Ok(matches!(self.is_equal(value.clone(), l.clone(), at)?, Some(Literal::Boolean(true)))),
+            Pattern::Binding(name) => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                Ok(true)
             },
-            (Some(a_t), Some(b_t)) => {
-                match (a_t, b_t) {
-                    (Literal::Boolean(a_tt), Literal::Boolean(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::StrongBoolean(a_tt), Literal::Boolean(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::Boolean(a_tt), Literal::StrongBoolean(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::StrongBoolean(a_tt), Literal::StrongBoolean(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::Number(a_tt), Literal::Number(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::StrongNumber(a_tt), Literal::Number(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::Number(a_tt), Literal::StrongNumber(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::StrongNumber(a_tt), Literal::StrongNumber(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::String(a_tt), Literal::String(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::StrongString(a_tt), Literal::StrongString(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
-                    },
-                    (Literal::String(a_tt), Literal::StrongString(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
+            Pattern::Type(type_, name) => {
+                let matched = Self::is_type(value, &type_.lexeme);
+                if let (true, Some(n)) = (matched, name) {
+                    bindings.push((n.lexeme.clone(), value.clone()));
+                }
+                Ok(matched)
+            },
+            Pattern::Class(class, name) => {
+                let matched = Self::is_instance_of(value, &class.lexeme);
+                if let (true, Some(n)) = (matched, name) {
+                    bindings.push((n.lexeme.clone(), value.clone()));
+                }
+                Ok(matched)
+            },
+            Pattern::List(elements, rest) => {
+                let items = match value {
+                    Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) => c,
+                    _ => return Ok(false)
+                };
+
+                let fits = match rest {
+                    Some(_) => items.len() >= elements.len(),
+                    None => items.len() == elements.len()
+                };
+                if !fits {
+                    return Ok(false);
+                }
+
+                for (element, item) in elements.iter().zip(items.iter()) {
+                    if !self.match_pattern(element, item, at, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                if let Some(Some(name)) = rest {
+                    let remaining = items[elements.len()..].to_vec();
+                    bindings.push((name.lexeme.clone(), Some(Literal::Collection(remaining))));
+                }
+                Ok(true)
+            },
+            Pattern::Fields(fields) => {
+                let instance = match value {
+                    Some(Literal::Instance(i)) => i,
+                    _ => return Ok(false)
+                };
+
+                for (name, field) in fields {
+                    let v = match instance.get_internal(name.clone(), self) {
+                        Ok(v) => v,
+                        Err(_) => return Ok(false)
+                    };
+                    if !self.match_pattern(field, &v, at, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            },
+            Pattern::Variant(enumeration, variant, patterns) => {
+                let v = match value {
+                    Some(Literal::Variant(v)) if v.enum_name == enumeration.lexeme && v.name == variant.lexeme => v,
+                    _ => return Ok(false)
+                };
+                if patterns.is_empty() {
+                    return Ok(true);
+                }
+                if patterns.len() != v.values.len() {
+                    return Ok(false);
+                }
+                for (p, x) in patterns.iter().zip(v.values.iter()) {
+                    if !self.match_pattern(p, x, at, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            },
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    let mut found = Vec::new();
+                    if self.match_pattern(alternative, value, at, &mut found)? {
+                        bindings.extend(found);
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    //like match_pattern, but a value that does not fit the pattern is an error rather than a miss
+    pub fn destructure(&mut self, pattern : &Pattern, value : &Option<Literal>, at : &Token, bindings : &mut Vec<(String, Option<Literal>)>) -> RuntimeError<()> {
+        match pattern {
+            Pattern::List(elements, rest) => {
+                let items = match value {
+                    Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) => c,
+                    v => return Err(LoxError::type_mismatch(at.clone(), &format!("Expected a list to destructure but got {}.", self.shown(v))))
+                };
+
+                match rest {
+                    Some(_) if items.len() < elements.len() => {
+                        return Err(LoxError::index_out_of_range(at.clone(), &format!("Expected at least {} elements but got {}.", elements.len(), items.len())));
                     },
-                    (Literal::StrongString(a_tt), Literal::String(b_tt)) => {
-                        Some(Literal::Boolean(a_tt != b_tt))
+                    None if items.len() != elements.len() => {
+                        return Err(LoxError::index_out_of_range(at.clone(), &format!("Expected {} elements but got {}.", elements.len(), items.len())));
                     },
-                    _ => { None }
+                    _ => {}
                 }
-                
+
+                for (element, item) in elements.iter().zip(items.iter()) {
+                    self.destructure(element, item, at, bindings)?;
+                }
+
+                if let Some(Some(name)) = rest {
+                    let remaining = items[elements.len()..].to_vec();
+                    bindings.push((name.lexeme.clone(), Some(Literal::Collection(remaining))));
+                }
+                Ok(())
+            },
+            Pattern::Fields(fields) => {
+                let instance = match value {
+                    Some(Literal::Instance(i)) => i,
+                    v => return Err(LoxError::type_mismatch(at.clone(), &format!("Expected an instance to destructure but got {}.", self.shown(v))))
+                };
+
+                for (name, field) in fields {
+                    let v = match instance.get_internal(name.clone(), self) {
+                        Ok(v) => v,
+                        Err(_) => return Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}' on {} instance.", name.lexeme, instance.class.name)))
+                    };
+                    self.destructure(field, &v, at, bindings)?;
+                }
+                Ok(())
             },
             _ => {
-                Some(Literal::Boolean(true))
+                match self.match_pattern(pattern, value, at, bindings)? {
+                    true => Ok(()),
+                    false => Err(LoxError::type_mismatch(at.clone(), &format!("Cannot destructure {} with this pattern.", self.shown(value))))
+                }
+            }
+        }
+    }
+
+    fn shown(&self, value : &Option<Literal>) -> String {
+        match value {
+            Some(Literal::Instance(i)) => format!("{} instance", i.class.name),
+            v => self.print_helper(v.clone(), false, 0)
+        }
+    }
+
+    fn slice_number(&mut self, bound : &Box<dyn Expr>, at : &Token) -> RuntimeError<i64> {
+        match self.evaluate(bound)? {
+            Some(Literal::Number(x)) | Some(Literal::StrongNumber(x)) => Self::slice_integer(x, at),
+            _ => Err(LoxError::type_mismatch(at.clone(), "Slice bounds must be numbers."))
+        }
+    }
+
+    fn slice_integer(x : f64, at : &Token) -> RuntimeError<i64> {
+        match x.fract() == 0.0 {
+            true => Ok(x as i64),
+            false => Err(LoxError::type_mismatch(at.clone(), &format!("Slice bounds must be integers but got {}.", x)))
+        }
+    }
+
+    //resolves a slice or range against a length into (start, stop, step), stop is exclusive and may be -1 when stepping backwards
+    fn slice_bounds(&mut self, selection : &Selection, len : usize) -> RuntimeError<(i64, i64, i64)> {
+        let (start, stop, step, inclusive, at) = match selection {
+            Selection::Slice(slice) => {
+                let step = match &slice.step {
+                    Some(v) => self.slice_number(v, &slice.colon)?,
+                    None => 1
+                };
+                let start = match &slice.start {
+                    Some(v) => Some(self.slice_number(v, &slice.colon)?),
+                    None => None
+                };
+                let stop = match &slice.stop {
+                    Some(v) => Some(self.slice_number(v, &slice.colon)?),
+                    None => None
+                };
+                (start, stop, step, false, &slice.colon)
+            },
+            Selection::Range(range, at) => {
+                let (start, stop, step) = (Self::slice_integer(range.start, at)?, Self::slice_integer(range.end, at)?, Self::slice_integer(range.step, at)?);
+                (Some(start), Some(stop), step, range.inclusive, *at)
+            }
+        };
+        if step == 0 {
+            return Err(LoxError::type_mismatch(at.clone(), "Slice step cannot be zero."));
+        }
+        
+        //negative bounds count back from the end like indexing does, anything past either end is clamped
+        let len = len as i64;
+        let (low, high) = if step > 0 { (0, len) } else { (-1, len - 1) };
+        let from_end = |v : i64| if v < 0 { v + len } else { v };
+        
+        let start = match start {
+            Some(v) => from_end(v).clamp(low, high),
+            None => if step > 0 { 0 } else { len - 1 }
+        };
+        let stop = match stop {
+            Some(v) if inclusive => (from_end(v) + step.signum()).clamp(low, high),
+            Some(v) => from_end(v).clamp(low, high),
+            None => if step > 0 { len } else { -1 }
+        };
+        Ok((start, stop, step))
+    }
+
+    fn slice_indices(start : i64, stop : i64, step : i64) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut i = start;
+        while (step > 0 && i < stop) || (step < 0 && i > stop) {
+            indices.push(i as usize);
+            i += step;
+        }
+        indices
+    }
+
+    fn chars_of(s : &str) -> Vec<Box<Option<Literal>>> {
+        s.chars().map(|c| Box::new(Some(Literal::String(String::from(c))))).collect()
+    }
+
+    fn join_chars(items : &[Box<Option<Literal>>]) -> String {
+        let mut o = String::new();
+        for c in items {
+            if let Some(Literal::String(s)) = &**c {
+                o.push_str(s);
+            }
+        }
+        o
+    }
+
+    fn slice(&mut self, object : Option<Literal>, selection : &Selection, at : &Token) -> RuntimeError<Option<Literal>> {
+        let items = match &object {
+            Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) => c.clone(),
+            Some(Literal::String(s)) | Some(Literal::StrongString(s)) => Self::chars_of(s),
+            _ => return Err(LoxError::type_mismatch(at.clone(), "Only lists and strings can be sliced."))
+        };
+        
+        let (start, stop, step) = self.slice_bounds(selection, items.len())?;
+        let picked : Vec<Box<Option<Literal>>> = Self::slice_indices(start, stop, step).into_iter().map(|i| items[i].clone()).collect();
+        
+        Ok(match object {
+            Some(Literal::StrongCollection(_)) => Some(Literal::StrongCollection(picked)),
+            Some(Literal::String(_)) => Some(Literal::String(Self::join_chars(&picked))),
+            Some(Literal::StrongString(_)) => Some(Literal::StrongString(Self::join_chars(&picked))),
+            _ => Some(Literal::Collection(picked))
+        })
+    }
+
+    //splices the value into a copy of the target and then stores that copy back the same way a plain assignment would
+    fn assign_slice(&mut self, expr : &IndexSet, slice : &Slice) -> RuntimeError<Option<Literal>> {
+        if expr.assign_type.is_some() {
+            return Err(LoxError::type_mismatch(expr.name.clone(), "Compound assignment is not supported on slices."));
+        }
+        
+        let object = self.evaluate(&expr.object)?;
+        let value = self.evaluate(&expr.value)?;
+        
+        let (mut items, replacement) = match (&object, &value) {
+            (Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)), Some(Literal::Collection(r)) | Some(Literal::StrongCollection(r))) => (c.clone(), r.clone()),
+            (Some(Literal::String(s)) | Some(Literal::StrongString(s)), Some(Literal::String(r)) | Some(Literal::StrongString(r))) => (Self::chars_of(s), Self::chars_of(r)),
+            (Some(Literal::Collection(_)) | Some(Literal::StrongCollection(_)), _) => {
+                return Err(LoxError::type_mismatch(expr.name.clone(), "Can only assign a list to a list slice."));
+            },
+            (Some(Literal::String(_)) | Some(Literal::StrongString(_)), _) => {
+                return Err(LoxError::type_mismatch(expr.name.clone(), "Can only assign a string to a string slice."));
+            },
+            _ => return Err(LoxError::type_mismatch(expr.name.clone(), "Only lists and strings can be sliced."))
+        };
+        
+        let (start, stop, step) = self.slice_bounds(&Selection::Slice(slice), items.len())?;
+        if step == 1 {
+            items.splice(start as usize..stop.max(start) as usize, replacement);
+        }
+        else {
+            let indices = Self::slice_indices(start, stop, step);
+            if indices.len() != replacement.len() {
+                return Err(LoxError::index_out_of_range(
+                    expr.name.clone(),
+                    &format!("Cannot assign {} elements to a slice of {} elements.", replacement.len(), indices.len())
+                ));
+            }
+            for (i, r) in indices.into_iter().zip(replacement) {
+                items[i] = r;
+            }
+        }
+        
+        let updated = Box::new(LiteralExp::new(match object {
+            Some(Literal::StrongCollection(_)) => Some(Literal::StrongCollection(items)),
+            Some(Literal::String(_)) => Some(Literal::String(Self::join_chars(&items))),
+            Some(Literal::StrongString(_)) => Some(Literal::StrongString(Self::join_chars(&items))),
+            _ => Some(Literal::Collection(items))
+        }));
+        
+        self.store(expr.object.as_ref(), updated, &expr.name)?;
+        Ok(value)
+    }
+
+    //writes a whole new value back to wherever target was read from, the same way a plain assignment would
+    fn store(&mut self, target : &dyn Expr, value : Box<dyn Expr>, at : &Token) -> RuntimeError<()> {
+        let store : Box<dyn Expr> = if let Some(v) = target.as_any().downcast_ref::<VarExpr>() {
+            Box::new(Assign::new(v.name.clone(), value, None))
+        }
+        else if let Some(v) = target.as_any().downcast_ref::<Get>() {
+            Box::new(Set::new(v.name.clone(), v.object.clone(), value, None))
+        }
+        else if let Some(v) = target.as_any().downcast_ref::<IndexGet>() {
+            Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value, None))
+        }
+        else {
+            return Err(LoxError::type_mismatch(at.clone(), "Invalid assignment target."));
+        };
+        
+        self.evaluate(&store)?;
+        Ok(())
+    }
+
+    //runs a method like __add__ on the instance, the outer None means its class doesn't define one
+    fn special_method(&mut self, instance : &LoxInstance, name : &str, arguments : Vec<Option<Literal>>, at : &Token) -> RuntimeError<Option<Option<Literal>>> {
+        let mut method = match instance.class.find_method(name.to_string()) {
+            Some(m) => m,
+            None => return Ok(None)
+        };
+        let method = method.bind(self, instance);
+        let depth = self.environment.depth();
+        let res = method.arrange(self, at, arguments, vec!())
+            .and_then(|arguments| method.call(self, at.clone(), arguments, true));
+        //a failing call doesn't clean up after itself, its scope mustn't leak into the caller's
+        if res.is_err() {
+            self.unwind_to(depth);
+        }
+        Ok(Some(res?))
+    }
+
+    //the operator methods a class can define, checked against the left operand only
+    fn operator_method(operator : TokenType) -> Option<&'static str> {
+        match operator {
+            TokenType::Plus => Some("__add__"),
+            TokenType::Minus => Some("__sub__"),
+            TokenType::Star => Some("__mul__"),
+            TokenType::Slash => Some("__div__"),
+            TokenType::Mod => Some("__mod__"),
+            TokenType::Less => Some("__lt__"),
+            TokenType::LessEqual => Some("__le__"),
+            TokenType::Greater => Some("__gt__"),
+            TokenType::GreaterEqual => Some("__ge__"),
+            _ => None
+        }
+    }
+
+    //instances with __str__ become that string, inside lists too, so print and 'as string' show them the way they ask
+    fn displayed(&mut self, value : Option<Literal>, at : &Token) -> RuntimeError<Option<Literal>> {
+        match value {
+            Some(Literal::Instance(i)) => {
+                match self.special_method(&i, "__str__", vec!(), at)? {
+                    Some(Some(Literal::String(s))) | Some(Some(Literal::StrongString(s))) => Ok(Some(Literal::String(s))),
+                    Some(v) => Err(LoxError::type_mismatch(at.clone(), &format!("__str__ of {} must return a string but returned {}.", i.class.name, Self::type_name(&v)))),
+                    None => Ok(Some(Literal::Instance(i)))
+                }
+            },
+            Some(Literal::Collection(c)) => {
+                let mut items = Vec::new();
+                for v in c {
+                    items.push(Box::new(self.displayed(*v, at)?));
+                }
+                Ok(Some(Literal::Collection(items)))
+            },
+            v => Ok(v)
+        }
+    }
+
+    //None when the value can never be a key, errors only come from a hash() method failing
+    fn map_key(&mut self, key : &Option<Literal>, at : &Token) -> RuntimeError<Option<MapKey>> {
+        match key {
+            Some(Literal::Instance(i)) => {
+                let hash = match self.special_method(i, "hash", vec!(), at)? {
+                    Some(h) => h,
+                    None => return Ok(None)
+                };
+                
+                match hash.as_ref().and_then(MapKey::of) {
+                    Some(k) => Ok(Some(MapKey::Instance(i.class.name.clone(), Box::new(k)))),
+                    None => Err(LoxError::type_mismatch(at.clone(), &format!("hash() of {} must return a number, string or bool.", i.class.name)))
+                }
+            },
+            Some(v) => Ok(MapKey::of(v)),
+            None => Ok(None)
+        }
+    }
+
+    fn require_key(&mut self, key : &Option<Literal>, at : &Token) -> RuntimeError<MapKey> {
+        match self.map_key(key, at)? {
+            Some(k) => Ok(k),
+            None => Err(LoxError::type_mismatch(at.clone(), &format!("Cannot use {} as a map key, keys must be numbers, strings, bools or instances with a hash() method.", self.shown(key))))
+        }
+    }
+
+    fn map_get(&mut self, map : &LoxMap, key : &Option<Literal>, at : &Token) -> RuntimeError<Option<Literal>> {
+        let hashed = self.require_key(key, at)?;
+        match map.get(&hashed) {
+            Some(v) => Ok(v.clone()),
+            None => Err(LoxError::index_out_of_range(at.clone(), &format!("Key '{}' not found in map.", self.shown(key))))
+        }
+    }
+
+    //xs[i] ??= v reads the element once and only stores when it was nil, the index and any temporary object are evaluated once
+    fn assign_entry_if_nil(&mut self, expr : &IndexSet, coll : Vec<Box<Option<Literal>>>) -> RuntimeError<Option<Literal>> {
+        let index : Box<dyn Expr> = Box::new(LiteralExp::new(self.evaluate(&expr.index)?));
+        let object = &expr.object;
+        let object : Box<dyn Expr> = match object.as_any().is::<VarExpr>() || object.as_any().is::<This>() || object.as_any().is::<Get>() || object.as_any().is::<IndexGet>() {
+            true => object.clone(),
+            false => Box::new(LiteralExp::new(self.evaluate(object)?))
+        };
+
+        let current = self.evaluate(&(Box::new(IndexGet::new(expr.name.clone(), index.clone(), object.clone())) as Box<dyn Expr>))?;
+        if current.is_some() {
+            return Ok(current);
+        }
+        self.visit_index_set_expr(&IndexSet::new(expr.name.clone(), index, object, expr.value.clone(), None), coll)
+    }
+
+    //__setindex__ does the storing, the instance keeps whatever it changed through its reference
+    fn assign_instance_entry(&mut self, expr : &IndexSet, instance : &LoxInstance, coll : Vec<Box<Option<Literal>>>) -> RuntimeError<Option<Literal>> {
+        let key = self.evaluate(&expr.index)?;
+        
+        let new_value = if !coll.is_empty() {
+            Some(Literal::Collection(coll))
+        }
+        else {
+            let value = self.evaluate(&expr.value)?;
+            match expr.assign_type {
+                Some(_) => {
+                    let current = match self.special_method(instance, "__index__", vec![key.clone()], &expr.name)? {
+                        Some(v) => Ok(v),
+                        None => Err(LoxError::type_mismatch(expr.name.clone(), &format!("{} has no __index__ method to read the current value.", instance.class.name)))
+                    };
+                    self.assign_value(current, value, expr.assign_type, expr.name.clone())?
+                },
+                None => value
             }
+        };
+        
+        self.special_method(instance, "__setindex__", vec![key, new_value.clone()], &expr.name)?;
+        Ok(new_value)
+    }
+
+    fn assign_map_entry(&mut self, expr : &IndexSet, mut map : LoxMap, coll : Vec<Box<Option<Literal>>>) -> RuntimeError<Option<Literal>> {
+        let key = self.evaluate(&expr.index)?;
+        let hashed = self.require_key(&key, &expr.name)?;
+        
+        //a nested index set hands back the already updated inner list in coll
+        let new_value = if !coll.is_empty() {
+            Some(Literal::Collection(coll))
+        }
+        else {
+            let value = self.evaluate(&expr.value)?;
+            match expr.assign_type {
+                Some(_) => {
+                    let current = self.map_get(&map, &key, &expr.name)?;
+                    self.assign_value(Ok(current), value, expr.assign_type, expr.name.clone())?
+                },
+                None => value
+            }
+        };
+        
+        if let Some(k) = key {
+            map.insert(hashed, k, new_value.clone());
+        }
+        self.store(expr.object.as_ref(), Box::new(LiteralExp::new(Some(Literal::Map(map)))), &expr.name)?;
+        Ok(new_value)
+    }
+
+    //maps take -= key to remove an entry and += map to merge, everything else is assign_helper
+    fn assign_value(&mut self, current : RuntimeError<Option<Literal>>, value : Option<Literal>, assign_type : Option<TokenType>, name : Token) -> RuntimeError<Option<Literal>> {
+        //x ??= y only gets this far once x turned out nil
+        if assign_type == Some(TokenType::QuestionQuestion) {
+            return Ok(value);
+        }
+        //v += w is v = v + w for an instance with __add__
+        if let (Ok(Some(Literal::Instance(i))), Some(operator)) = (&current, assign_type) {
+            if let Some(method) = Self::operator_method(operator) {
+                if let Some(v) = self.special_method(i, method, vec![value.clone()], &name)? {
+                    return Ok(v);
+                }
+            }
+        }
+        match (&current, assign_type) {
+            (Ok(Some(Literal::Map(m))), Some(TokenType::Minus)) => {
+                let mut m = m.clone();
+                let key = self.require_key(&value, &name)?;
+                m.remove(&key);
+                Ok(Some(Literal::Map(m)))
+            },
+            (Ok(Some(Literal::Map(m))), Some(TokenType::Plus)) => {
+                let mut m = m.clone();
+                match value {
+                    Some(Literal::Map(other)) => {
+                        for (k, v) in other.entries() {
+                            let key = self.require_key(&Some(k.clone()), &name)?;
+                            m.insert(key, k.clone(), v.clone());
+                        }
+                        Ok(Some(Literal::Map(m)))
+                    },
+                    _ => Err(LoxError::type_mismatch(name.clone(), "Can only add a map to a map."))
+                }
+            },
+            _ => Self::assign_helper(current, value, assign_type, name)
+        }
+    }
+
+    //'in' checks the keys of a map, the items of a list or the text of a string
+    fn contains(&mut self, item : Option<Literal>, container : Option<Literal>, at : &Token) -> RuntimeError<bool> {
+        match container {
+            Some(Literal::Map(m)) => {
+                match self.map_key(&item, at)? {
+                    Some(k) => Ok(m.contains(&k)),
+                    None => Ok(false)
+                }
+            },
+            Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) => {
+                for v in c {
+                    if let Some(Literal::Boolean(true)) = self.is_equal(item.clone(), *v, at)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            },
+            Some(Literal::String(s)) | Some(Literal::StrongString(s)) => {
+                match item {
+                    Some(Literal::String(sub)) | Some(Literal::StrongString(sub)) => Ok(s.contains(&sub)),
+                    _ => Err(LoxError::type_mismatch(at.clone(), "Only a string can be looked for in a string."))
+                }
+            },
+            Some(Literal::Range(r)) => {
+                match item {
+                    Some(Literal::Number(x)) | Some(Literal::StrongNumber(x)) => Ok(r.contains(x)),
+                    _ => Ok(false)
+                }
+            },
+            _ => Err(LoxError::type_mismatch(at.clone(), "Right side of 'in' must be a map, list, range or string."))
         }
     }
 
@@ -566,6 +1266,136 @@ impl Interpreter {
         }
     }
     
+    //a failing call leaves its scopes behind, this drops back to the one a try started in and keeps what was assigned there
+    fn unwind_to(&mut self, depth : usize) {
+        while self.environment.depth() > depth {
+            match self.environment.enclosing.take() {
+                Some(e) => self.environment = *e,
+                None => break
+            }
+        }
+    }
+
+    //runs statements in a new scope, optionally with a name already bound, and leaves the scope even when they fail
+    fn execute_scoped(&mut self, statements : &Vec<Box<dyn Stmt>>, binding : Option<(String, Option<Literal>)>) -> RuntimeError<Option<Literal>> {
+        let depth = self.environment.depth();
+        let frames = self.frames.len();
+        self.environment = Environment::new_with_enclosing(self.environment.clone());
+        if let Some((name, value)) = binding {
+            let mut e = self.environment.clone();
+            e.define(self, name, value);
+            self.environment = e;
+        }
+
+        let res = self.execute_block(statements);
+        self.unwind_to(depth);
+        self.frames.truncate(frames);
+        res
+    }
+
+    //what a catch block sees, thrown values as they were and interpreter errors as instances of the Error class
+    fn caught_value(&mut self, error : LoxError) -> RuntimeError<Option<Literal>> {
+        if let LoxError::Thrown(_, value) = error {
+            return Ok(*value);
+        }
+
+        let at = error.token().clone();
+        let arguments = vec!(
+            Some(Literal::String(error.message().to_string())),
+            Some(Literal::String(error.kind().to_string())),
+            Some(Literal::Number(at.line as f64))
+        );
+        match self.environment.get(self, Token::new(TokenType::Identifier, "Error", None, at.line)) {
+            Ok(Some(Literal::Class(class))) => {
+                let arguments = class.arrange(self, &at, arguments, vec!())?;
+                class.call(self, at, arguments, true)
+            },
+            _ => Ok(arguments[0].clone())
+        }
+    }
+
+    //next to the importing file first, then each search path in order
+    fn resolve_module(&self, at : &Token, path : &str) -> RuntimeError<PathBuf> {
+        let mut bases = vec!(self.loading.last().and_then(|f| f.parent()).map(|d| d.to_path_buf()).unwrap_or_default());
+        bases.extend(self.search_paths.iter().cloned());
+
+        for base in bases {
+            let candidate = base.join(path);
+            if candidate.is_file() {
+                return candidate.canonicalize()
+                    .map_err(|e| LoxError::import(at.clone(), &format!("Cannot open module '{}': {}.", path, e)));
+            }
+        }
+        Err(LoxError::import(at.clone(), &format!("Cannot find module '{}'.", path)))
+    }
+
+    fn module_file_name(file : &Path) -> String {
+        file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+
+    //runs a file in a scope of its own the first time it's imported, later imports get the cached exports
+    fn load_module(&mut self, at : &Token, file : PathBuf) -> RuntimeError<LoxModule> {
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.loading.iter().position(|f| *f == file) {
+            let mut cycle : Vec<String> = self.loading[start..].iter().map(|f| Self::module_file_name(f)).collect();
+            cycle.push(Self::module_file_name(&file));
+            return Err(LoxError::import(at.clone(), &format!("Circular import: {}.", cycle.join(" -> "))));
+        }
+
+        let source = fs::read_to_string(&file)
+            .map_err(|e| LoxError::import(at.clone(), &format!("Cannot read module '{}': {}.", file.display(), e)))?;
+        self.sources.push((file.display().to_string(), source.clone()));
+        let mut scanner = Scanner::new_with_file(source, self.sources.len()-1);
+        let _ = scanner.scan_tokens();
+        let mut parser = Parser::new(scanner.tokens.clone());
+        let statements = parser.parse();
+
+        let mut diagnostics = scanner.diagnostics;
+        diagnostics.extend(parser.diagnostics);
+        diagnostics.sort();
+        if let Some(e) = diagnostics.first() {
+            return Err(e);
+        }
+        let statements = statements?;
+
+        //the module runs in its own scope above the standard library, so what it declares wins over a prelude name like PI
+        let prelude = self.prelude.clone().unwrap_or_else(Environment::new);
+        let importer = std::mem::replace(&mut self.environment, Environment::new_with_enclosing(prelude));
+        self.suspended.push(importer);
+        self.loading.push(file.clone());
+        let res = self.interpret(statements);
+        self.loading.pop();
+        let importer = self.suspended.pop().unwrap();
+        let scope = std::mem::replace(&mut self.environment, importer);
+        res?;
+
+        //only the module's own scope is kept, the standard library comes from whoever calls in
+        //functions drop their closures here, LoxModule::get hands them the whole module so they see what was declared after them too
+        let mut exports = Environment::new();
+        exports.types = scope.types.clone();
+        for (name, value) in &scope.values {
+            let value = match value {
+                Some(Literal::Function(f)) => match f.as_any().downcast_ref::<LoxFunction>() {
+                    Some(function) if matches!(function.f_type, FunctionType::Normal) => {
+                        let mut function = function.clone();
+                        function.closure = Environment::new();
+                        Some(Literal::Function(Box::new(function)))
+                    },
+                    _ => value.clone()
+                },
+                _ => value.clone()
+            };
+            exports.values.insert(name.clone(), value);
+        }
+
+        let name = file.file_stem().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let module = LoxModule::new(name, file.display().to_string(), exports);
+        self.modules.insert(file, module.clone());
+        Ok(module)
+    }
+
     pub fn execute_block(&mut self, statements : &Vec<Box<dyn Stmt>>) -> RuntimeError<Option<Literal>> {
         let mut return_val = None;
         
@@ -583,12 +1413,15 @@ impl Interpreter {
                         continue;
                     }
                 },
-                Err((e, v)) => {
-                    return Err((e, v))
+                Err(e) => {
+                    return Err(e)
                 }
             }
         }
-        self.collect_garbage();
+        match &return_val {
+            Some(Literal::Return(v)) => self.collect_garbage_keeping(&Some(*v.clone())),
+            _ => self.collect_garbage()
+        }
         Ok(return_val)
     }
 }
@@ -605,6 +1438,17 @@ impl StmtVisitor for Interpreter {
                 Err(e) => return Err(e)
             };
             
+            if let Some(annotation) = &stmt.annotation {
+                if !annotation.accepts(&value) {
+                    return Err(LoxError::type_mismatch(stmt.name.clone(), &format!(
+                        "Cannot assign {} to '{}' of type {}.",
+                        Self::type_name(&value),
+                        stmt.name.lexeme,
+                        annotation
+                    )));
+                }
+            }
+            
             match (value.clone(), stmt.binding.clone().type_) {
                 (Some(Literal::Number(x)), TokenType::Number) => value = Some(Literal::StrongNumber(x)),
                 (Some(Literal::StrongNumber(_)), TokenType::Number) => {}
@@ -614,7 +1458,8 @@ impl StmtVisitor for Interpreter {
                 (Some(Literal::StrongString(_)), TokenType::String) => {}
                 (Some(_), TokenType::Var) => { },
                 (None, TokenType::Var) => { },
-                _ => return Err((stmt.binding.clone(), "Invalid variable declaration.".to_string()))
+                _ if stmt.annotation.is_some() => { },
+                _ => return Err(LoxError::type_mismatch(stmt.binding.clone(), "Invalid variable declaration."))
             }
 
             let mut e = self.environment.clone();
@@ -626,11 +1471,16 @@ impl StmtVisitor for Interpreter {
             e.define(self, stmt.name.lexeme.clone(), None);
             self.environment = e;
         }
+        
+        if let Some(annotation) = &stmt.annotation {
+            self.environment.annotate(stmt.name.lexeme.clone(), annotation.clone());
+        }
         Ok(None)
     }
     
     fn visit_print_stmt(&mut self, stmt :&Print) -> RuntimeError<Option<Literal>> {
         let value = self.evaluate(&stmt.expression)?;
+        let value = self.displayed(value, &stmt.keyword)?;
         
         let out = self.print_helper(value, stmt.newline, 0);
         print!("{}", out);
@@ -660,7 +1510,7 @@ impl StmtVisitor for Interpreter {
                         super_class = Some(Box::new(v2.clone()));
                     }
                 },
-                _ => return Err((stmt.name.clone(), "Super-class must be a class.".to_string()))
+                _ => return Err(LoxError::type_mismatch(stmt.name.clone(), "Super-class must be a class."))
             }
         }
         let mut e = self.environment.clone();
@@ -675,13 +1525,223 @@ impl StmtVisitor for Interpreter {
             }
         }
         
-        let class = Box::new(LoxClass::new(stmt.name.lexeme.clone(), methods, super_class));
+        let mut class = Box::new(LoxClass::new(stmt.name.lexeme.clone(), methods, super_class, stmt.doc.clone()));
+        class.abstracts = stmt.abstracts.iter().map(|f| f.name.lexeme.clone()).collect();
+        for method in &stmt.statics {
+            if let Some(v) = method.as_any().downcast_ref::<Function>() {
+                class.static_methods.insert(v.name.lexeme.clone(), LoxFunction::new(v.clone(), self.environment.clone(), FunctionType::Static, false));
+            }
+        }
+        for v in &stmt.getters {
+            class.getters.insert(v.name.lexeme.clone(), LoxFunction::new(v.clone(), self.environment.clone(), FunctionType::Method, false));
+        }
+        for v in &stmt.setters {
+            class.setters.insert(v.name.lexeme.clone(), LoxFunction::new(v.clone(), self.environment.clone(), FunctionType::Method, false));
+        }
+        //the names go in first so a subclass declared later sees them, values come once the class itself exists
+        if !stmt.fields.is_empty() {
+            class.statics = Some(self.statics.len());
+            self.statics.push(stmt.fields.iter().map(|(name, _)| (name.lexeme.clone(), None)).collect());
+        }
+        
+        for i in &stmt.interfaces {
+            match self.evaluate(i)? {
+                Some(Literal::Interface(interface)) => class.interfaces.push(*interface),
+                _ => return Err(LoxError::type_mismatch(stmt.name.clone(), "Can only implement interfaces."))
+            }
+        }
+        
+        //an abstract method counts, whoever subclasses it has to fill it in before anything can be created
+        for interface in &class.interfaces {
+            for (name, arity) in &interface.methods {
+                match class.find_method(name.clone()) {
+                    Some(method) if !method.arity().covers(arity) => {
+                        return Err(LoxError::type_mismatch(stmt.name.clone(), &format!(
+                            "Method '{}' of class '{}' takes {} arguments but interface '{}' expects {}.", name, class.name, method.arity(), interface.name, arity
+                        )));
+                    },
+                    Some(_) => { },
+                    None if class.is_abstract(name) => { },
+                    None => {
+                        return Err(LoxError::undefined_property(stmt.name.clone(), &format!(
+                            "Class '{}' is missing method '{}' from interface '{}'.", class.name, name, interface.name
+                        )));
+                    }
+                }
+            }
+        }
+        
+        let statics = class.statics;
+        e.assign(self, stmt.name.clone(), Some(Literal::Class(class)))?;
+        self.environment = e;
+        
+        if let Some(i) = statics {
+            for (name, value) in &stmt.fields {
+                if let Some(value) = value {
+                    let value = self.evaluate(value)?;
+                    self.statics[i].insert(name.lexeme.clone(), value);
+                }
+            }
+        }
+        Ok(None)
+    }
+    
+    fn visit_match_stmt(&mut self, stmt : &Match) -> RuntimeError<Option<Literal>> {
+        let value = self.evaluate(&stmt.value)?;
+
+        for arm in &stmt.arms {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(&arm.pattern, &value, &stmt.keyword, &mut bindings)? {
+                continue;
+            }
+
+            let depth = self.environment.depth();
+            self.environment = Environment::new_with_enclosing(self.environment.clone());
+            for (name, v) in bindings {
+                let mut e = self.environment.clone();
+                e.define(self, name, v);
+                self.environment = e;
+            }
+
+            let passed = match &arm.guard {
+                Some(guard) => {
+                    match self.evaluate(guard) {
+                        Ok(v) => Ok(matches!(self.is_truthy(v), Some(Literal::Boolean(true)) | Some(Literal::StrongBoolean(true)))),
+                        Err(e) => Err(e)
+                    }
+                },
+                None => Ok(true)
+            };
+
+            let res = match passed {
+                Ok(true) => Some(self.execute(&arm.body)),
+                Ok(false) => None,
+                Err(e) => Some(Err(e))
+            };
+
+            //a failed call in the guard or body can leave its scopes behind too
+            self.unwind_to(depth);
+
+            if let Some(res) = res {
+                return res;
+            }
+        }
+
+        Err(LoxError::non_exhaustive_match(stmt.keyword.clone(), &format!("Non-exhaustive match, no arm matched {}.", self.shown(&value))))
+    }
+
+    fn visit_enum_stmt(&mut self, stmt : &Enum) -> RuntimeError<Option<Literal>> {
+        let variants = stmt.variants.iter()
+            .map(|(name, fields)| (name.lexeme.clone(), fields.iter().map(|f| f.lexeme.clone()).collect()))
+            .collect();
+        let enumeration = LoxEnum::new(stmt.name.lexeme.clone(), variants, stmt.doc.clone());
+        
+        let mut e = self.environment.clone();
+        e.define(self, stmt.name.lexeme.clone(), Some(Literal::Enum(Box::new(enumeration))));
+        self.environment = e;
+        Ok(None)
+    }
+
+    fn visit_throw_stmt(&mut self, stmt : &Throw) -> RuntimeError<Option<Literal>> {
+        let mut value = self.evaluate(&stmt.value)?;
+
+        //errors made without a line get the one they're thrown from
+        if let Some(Literal::Instance(i)) = &mut value {
+            if Self::is_instance_of(&Some(Literal::Instance(i.clone())), "Error") && matches!(i.fields.get("line"), Some(None) | None) {
+                i.fields.insert("line".to_string(), Some(Literal::Number(stmt.keyword.line as f64)));
+                if let Some(a) = i.address {
+                    self.references[a] = Some(Literal::Instance(i.clone()));
+                }
+            }
+        }
+
+        let shown = self.displayed(value.clone(), &stmt.keyword)?;
+        let message = format!("Uncaught {}.", self.print_helper(shown, false, 0));
+        Err(LoxError::thrown(stmt.keyword.clone(), &message, value))
+    }
+
+    fn visit_try_stmt(&mut self, stmt : &Try) -> RuntimeError<Option<Literal>> {
+        let mut res = self.execute_scoped(&stmt.body, None);
+
+        if let Some((name, body)) = &stmt.catch {
+            res = match res {
+                Err(error) => {
+                    let value = self.caught_value(error)?;
+                    self.execute_scoped(body, Some((name.lexeme.clone(), value)))
+                },
+                v => v
+            };
+        }
+
+        //finally always runs, a return, break or error from it wins over whatever came before
+        if let Some(finally) = &stmt.finally {
+            if let Some(v) = self.execute_scoped(finally, None)? {
+                return Ok(Some(v));
+            }
+        }
+        res
+    }
+
+    fn visit_import_stmt(&mut self, stmt : &Import) -> RuntimeError<Option<Literal>> {
+        let path = match &stmt.path.literal {
+            Some(Literal::String(s)) => s.clone(),
+            _ => return Err(LoxError::type_mismatch(stmt.path.clone(), "Module path must be a string."))
+        };
+        let file = self.resolve_module(&stmt.path, &path)?;
+        let module = self.load_module(&stmt.path, file)?;
+
+        let mut bindings = Vec::new();
+        if stmt.names.is_empty() {
+            let name = match &stmt.alias {
+                Some(alias) => alias.lexeme.clone(),
+                None => module.name.clone()
+            };
+            let valid = name.chars().next().is_some_and(Scanner::is_alpha) && name.chars().all(Scanner::is_alpha_numeric);
+            if !valid {
+                return Err(LoxError::import(stmt.path.clone(), &format!("'{}' can't be used as a name, import it with 'as'.", name)));
+            }
+            bindings.push((name, Some(Literal::Module(Box::new(module)))));
+        }
+        else {
+            for name in &stmt.names {
+                bindings.push((name.lexeme.clone(), module.get(name, self)?));
+            }
+        }
+
+        for (name, value) in bindings {
+            let mut e = self.environment.clone();
+            e.define(self, name, value);
+            self.environment = e;
+        }
+        Ok(None)
+    }
+
+    fn visit_interface_stmt(&mut self, stmt : &Interface) -> RuntimeError<Option<Literal>> {
+        let methods = stmt.methods.iter()
+            .map(|m| (m.name.lexeme.clone(), Arity::of(&m.params)))
+            .collect();
+        let interface = LoxInterface::new(stmt.name.lexeme.clone(), methods, stmt.doc.clone());
         
-        e.assign(self, stmt.name.clone(), Some(Literal::Class(class)))?;
+        let mut e = self.environment.clone();
+        e.define(self, stmt.name.lexeme.clone(), Some(Literal::Interface(Box::new(interface))));
         self.environment = e;
         Ok(None)
     }
-    
+
+    fn visit_destructure_stmt(&mut self, stmt : &Destructure) -> RuntimeError<Option<Literal>> {
+        let value = self.evaluate(&stmt.value)?;
+
+        let mut bindings = Vec::new();
+        self.destructure(&stmt.pattern, &value, &stmt.keyword, &mut bindings)?;
+
+        for (name, v) in bindings {
+            let mut e = self.environment.clone();
+            e.define(self, name, v);
+            self.environment = e;
+        }
+        Ok(None)
+    }
+
     fn visit_if_stmt(&mut self, stmt : &If) -> RuntimeError<Option<Literal>> {
         let eval = self.evaluate(&stmt.condition)?;
         
@@ -790,7 +1850,7 @@ impl StmtVisitor for Interpreter {
                         value = Some(Literal::Return(Box::new(i)));
                     }
                 },
-                Err((e, v)) => return Err((e, v))
+                Err(e) => return Err(e)
             }
         }
         Ok(value)
@@ -813,6 +1873,12 @@ impl ExprVisitor for Interpreter {
         let a = left;
         let b = right;
 
+        if let (Some(Literal::Instance(i)), Some(name)) = (&a, Self::operator_method(expr.operator.type_)) {
+            if let Some(v) = self.special_method(i, name, vec![b.clone()], &expr.operator)? {
+                return Ok(v);
+            }
+        }
+
         match expr.operator.type_ {
             TokenType::Minus => {
                 match (a, b) {
@@ -826,7 +1892,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::StrongNumber(x - y)))
                     }
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             },
@@ -854,7 +1920,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::String(format!("{}{}", x, y))))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers Or Strings.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers Or Strings."))
                     }
                 }
             }
@@ -870,7 +1936,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::StrongNumber(x / y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             }
@@ -886,7 +1952,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::StrongNumber(x * y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             },
@@ -902,7 +1968,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::StrongNumber(x.rem_euclid(y))))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             }
@@ -921,7 +1987,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::Boolean(x > y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             },
@@ -940,7 +2006,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::Boolean(x >= y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             },
@@ -959,7 +2025,7 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::Boolean(x < y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             },
@@ -978,54 +2044,42 @@ impl ExprVisitor for Interpreter {
                         Ok(Some(Literal::Boolean(x <= y)))
                     },
                     _ => {
-                        Err((expr.operator.clone(), "Operands must be Numbers.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be Numbers."))
                     }
                 }
             }
+            TokenType::BangEqual if matches!(a, Some(Literal::Instance(_))) => {
+                let equal = self.is_equal(a, b, &expr.operator)?;
+                Ok(self.is_truthy_flip(equal))
+            }
             TokenType::BangEqual => {
                 Ok(self.is_not_equal(a, b))
             }
             TokenType::EqualEqual => {
-                Ok(self.is_equal(a, b))
+                self.is_equal(a, b, &expr.operator)
+            }
+            TokenType::In => {
+                Ok(Some(Literal::Boolean(self.contains(a, b, &expr.operator)?)))
             }
             TokenType::Is => {
-                match (a, b) {
-                    (Some(Literal::Number(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "num" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
-                        }
-                    }
-                    (Some(Literal::StrongNumber(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "num" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
-                        }
-                    }
-                    (Some(Literal::String(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "string" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
-                        }
-                    }
-                    (Some(Literal::StrongString(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "string" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
-                        }
-                    }
-                    (Some(Literal::Boolean(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "bool" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
-                        }
-                    }
-                    (Some(Literal::StrongBoolean(_)), Some(Literal::Keyword(key))) => {
-                        match key.as_ref() { 
-                            "bool" => Ok(Some(Literal::Boolean(true))),
-                            _ => Ok(Some(Literal::Boolean(false)))
+                let variant = match &a {
+                    Some(Literal::Variant(v)) => Some(v),
+                    _ => None
+                };
+                match b {
+                    Some(Literal::Keyword(key)) => Ok(Some(Literal::Boolean(Self::is_type(&a, &key)))),
+                    Some(Literal::Class(c)) => Ok(Some(Literal::Boolean(Self::is_instance_of(&a, &c.name)))),
+                    Some(Literal::Interface(i)) => Ok(Some(Literal::Boolean(Self::is_instance_of(&a, &i.name)))),
+                    Some(Literal::Enum(e)) => Ok(Some(Literal::Boolean(variant.is_some_and(|v| v.enum_name == e.name)))),
+                    Some(Literal::Variant(other)) => {
+                        Ok(Some(Literal::Boolean(variant.is_some_and(|v| v.enum_name == other.enum_name && v.name == other.name))))
+                    },
+                    Some(Literal::Function(f)) => {
+                        match f.as_any().downcast_ref::<VariantConstructor>() {
+                            Some(c) => Ok(Some(Literal::Boolean(variant.is_some_and(|v| v.enum_name == c.variant.enum_name && v.name == c.variant.name)))),
+                            None => Ok(Some(Literal::Boolean(false)))
                         }
-                    }
+                    },
                     _ => Ok(Some(Literal::Boolean(false)))
                 }
             }
@@ -1043,7 +2097,7 @@ impl ExprVisitor for Interpreter {
                                     Ok(Some(Literal::Boolean(false)))
                                 }
                             }
-                            _ => Err((expr.operator.clone(), "Operands must be valid.".to_string()))
+                            _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be valid."))
                         }
                     },
                     (Some(Literal::String(x)), Some(Literal::Keyword(key))) => {
@@ -1063,7 +2117,7 @@ impl ExprVisitor for Interpreter {
                                     Ok(Some(Literal::Boolean(false)))
                                 }
                             }
-                            _ => Err((expr.operator.clone(), "Operands must be valid.".to_string()))
+                            _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be valid."))
                         }
                     },
                     (Some(Literal::Boolean(x)), Some(Literal::Keyword(key))) => {
@@ -1087,13 +2141,18 @@ impl ExprVisitor for Interpreter {
                             "bool" => {
                                 Ok(Some(Literal::Boolean(x)))
                             }
-                            _ => Err((expr.operator.clone(), "Operands must be valid.".to_string()))
+                            _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be valid."))
                         }
                     },
-                    _ => Err((expr.operator.clone(), "Operands must be valid.".to_string()))
+                    //anything else prints the way print would, so interpolation works on any value
+                    (v, Some(Literal::Keyword(key))) if key == "string" => {
+                        let v = self.displayed(v, &expr.operator)?;
+                        Ok(Some(Literal::String(self.print_helper(v, false, 0))))
+                    },
+                    _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Operands must be valid."))
                 }
             }
-            _ => Err((expr.operator.clone(), "Invalid Operands".to_string()))
+            _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Invalid Operands"))
         }
     }
 
@@ -1109,7 +2168,7 @@ impl ExprVisitor for Interpreter {
             }
         }
         else {
-            Err((expr.operator_a.clone(), "Invalid condition for Ternary".to_string()))
+            Err(LoxError::type_mismatch(expr.operator_a.clone(), "Invalid condition for Ternary"))
         }
     }
 
@@ -1120,6 +2179,17 @@ impl ExprVisitor for Interpreter {
     fn visit_unary_expr(&mut self, expr : &Unary) -> RuntimeError<Option<Literal>> {
         let right = self.evaluate(&expr.right)?;
         
+        let special = match expr.operator.type_ {
+            TokenType::Minus => Some("__neg__"),
+            TokenType::Hash => Some("__len__"),
+            _ => None
+        };
+        if let (Some(Literal::Instance(i)), Some(name)) = (&right, special) {
+            if let Some(v) = self.special_method(i, name, vec!(), &expr.operator)? {
+                return Ok(v);
+            }
+        }
+        
         match expr.operator.type_ {
             TokenType::Minus => {
                 match right {
@@ -1127,11 +2197,11 @@ impl ExprVisitor for Interpreter {
                         match val {
                             Literal::Number(x) => Ok(Some(Literal::Number(-x))),
                             Literal::StrongNumber(x) => Ok(Some(Literal::StrongNumber(-x))),
-                            _ => Err((expr.operator.clone(), "Operand must be a Number.".to_string()))
+                            _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Operand must be a Number."))
                         }
                     }
                     None => {
-                        Err((expr.operator.clone(), "Operand must be a Number.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operand must be a Number."))
                     }
                 }
             },
@@ -1141,12 +2211,18 @@ impl ExprVisitor for Interpreter {
                         if let Literal::Collection(x) = val {
                             Ok(Some(Literal::Number(x.len() as f64)))
                         } 
+                        else if let Literal::Map(m) = val {
+                            Ok(Some(Literal::Number(m.len() as f64)))
+                        }
+                        else if let Literal::Range(r) = val {
+                            Ok(Some(Literal::Number(r.len() as f64)))
+                        }
                         else {
-                            Err((expr.operator.clone(), "Operand must be a List.".to_string()))
+                            Err(LoxError::type_mismatch(expr.operator.clone(), "Operand must be a List."))
                         }
                     }
                     None => {
-                        Err((expr.operator.clone(), "Operand must be a List.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "Operand must be a List."))
                     }
                 }
             }
@@ -1167,7 +2243,7 @@ impl ExprVisitor for Interpreter {
                             else if let Some(i) = expr.right.as_any().downcast_ref::<Get>() {
                                 let object = self.evaluate(&i.object)?;
                                 if let Some(Literal::Instance(mut v)) = object {
-                                    v.set(i.name.clone(), value.clone(), self);
+                                    v.set(i.name.clone(), value.clone(), self)?;
                                     if let Some(as_var) = i.object.as_any().downcast_ref::<VarExpr>() {
                                         let mut e = self.environment.clone();
                                         e.assign(self, as_var.name.clone(), Some(Literal::Instance(v.clone())))?;
@@ -1179,6 +2255,9 @@ impl ExprVisitor for Interpreter {
                                         self.environment = e;
                                     }
                                 }
+                                else if let Some(Literal::Class(c)) = object {
+                                    c.set_static(i.name.clone(), value.clone(), self)?;
+                                }
                             }
                             else if let Some(i) = expr.right.as_any().downcast_ref::<IndexGet>() {
                                 let object = self.evaluate(&i.object)?;
@@ -1187,7 +2266,7 @@ impl ExprVisitor for Interpreter {
                                         v[index as usize] = Box::new(value.clone());
                                     }
                                     else {
-                                        return Err((expr.operator.clone(), "Index must be a number type.".to_string()))
+                                        return Err(LoxError::type_mismatch(expr.operator.clone(), "Index must be a number type."))
                                     }
                                 
                                     if let Some(as_var) = i.object.as_any().downcast_ref::<VarExpr>() {
@@ -1200,11 +2279,11 @@ impl ExprVisitor for Interpreter {
                             Ok(Some(Literal::Number(x)))
                         } 
                         else {
-                            Err((expr.operator.clone(), "OperAnd must be a Number.".to_string()))
+                            Err(LoxError::type_mismatch(expr.operator.clone(), "OperAnd must be a Number."))
                         }
                     }
                     None => {
-                        Err((expr.operator.clone(), "OperAnd must be a Number.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "OperAnd must be a Number."))
                     }
                 }
             },
@@ -1221,7 +2300,7 @@ impl ExprVisitor for Interpreter {
                             else if let Some(i) = expr.right.as_any().downcast_ref::<Get>() {
                                 let object = self.evaluate(&i.object)?;
                                 if let Some(Literal::Instance(mut v)) = object {
-                                    v.set(i.name.clone(), value.clone(), self);
+                                    v.set(i.name.clone(), value.clone(), self)?;
                                     if let Some(as_var) = i.object.as_any().downcast_ref::<VarExpr>() {
                                         let mut e = self.environment.clone();
                                         e.assign(self, as_var.name.clone(), Some(Literal::Instance(v.clone())))?;
@@ -1233,6 +2312,9 @@ impl ExprVisitor for Interpreter {
                                         self.environment = e;
                                     }
                                 }
+                                else if let Some(Literal::Class(c)) = object {
+                                    c.set_static(i.name.clone(), value.clone(), self)?;
+                                }
                             }
                             else if let Some(i) = expr.right.as_any().downcast_ref::<IndexGet>() {
                                 let object = self.evaluate(&i.object)?;
@@ -1242,7 +2324,7 @@ impl ExprVisitor for Interpreter {
                                         v[((index as i32).rem_euclid(len)) as usize] = Box::new(value.clone());
                                     }
                                     else {
-                                        return Err((expr.operator.clone(), "Index must be a number type.".to_string()))
+                                        return Err(LoxError::type_mismatch(expr.operator.clone(), "Index must be a number type."))
                                     }
                                 
                                     if let Some(as_var) = i.object.as_any().downcast_ref::<VarExpr>() {
@@ -1255,16 +2337,16 @@ impl ExprVisitor for Interpreter {
                             Ok(Some(Literal::Number(x)))
                         } 
                         else {
-                            Err((expr.operator.clone(), "OperAnd must be a Number.".to_string()))
+                            Err(LoxError::type_mismatch(expr.operator.clone(), "OperAnd must be a Number."))
                         }
                     }
                     None => {
-                        Err((expr.operator.clone(), "OperAnd must be a Number.".to_string()))
+                        Err(LoxError::type_mismatch(expr.operator.clone(), "OperAnd must be a Number."))
                     }
                 }
             },
             _ => {
-                Err((expr.operator.clone(), "OperAnd type not found.".to_string()))
+                Err(LoxError::type_mismatch(expr.operator.clone(), "OperAnd type not found."))
             }
         }
     }
@@ -1305,7 +2387,7 @@ impl ExprVisitor for Interpreter {
         .get(self, Token::new(TokenType::Identifier, "this", None, expr.keyword.line))? {
             Some(Literal::Instance(v)) => v,
             _ => {
-                return Err((expr.keyword.clone(), "Could not find current this.".to_string()))
+                return Err(LoxError::undefined_variable(expr.keyword.clone(), "Could not find current this."))
             }
         };
         
@@ -1322,19 +2404,25 @@ impl ExprVisitor for Interpreter {
                     ))
                 }
                 else {
-                    Err((expr.keyword.clone(), "Could not find current this.".to_string()))
+                    Err(LoxError::undefined_variable(expr.keyword.clone(), "Could not find current this."))
                 }
             },
             _ => { 
-                Err((expr.keyword.clone(), "Could not find current this.".to_string()))
+                Err(LoxError::undefined_variable(expr.keyword.clone(), "Could not find current this."))
             }
         }
     }
     
     fn visit_assign_expr(&mut self, expr : &Assign) -> RuntimeError<Option<Literal>> {
+        if expr.assign_type == Some(TokenType::QuestionQuestion) {
+            let current = self.environment.get(self, expr.name.clone())?;
+            if current.is_some() {
+                return Ok(current);
+            }
+        }
         let value = self.evaluate(&expr.value)?.clone();
         
-        let new_value = Self::assign_helper(self.environment.get(self, expr.name.clone()), value.clone(), expr.assign_type.clone(), expr.name.clone())?;
+        let new_value = self.assign_value(self.environment.get(self, expr.name.clone()), value.clone(), expr.assign_type, expr.name.clone())?;
         
         let mut e = self.environment.clone();
         e.assign(self, expr.name.clone(), new_value.clone())?;
@@ -1342,6 +2430,20 @@ impl ExprVisitor for Interpreter {
         Ok(new_value)
     }
     
+    fn visit_assign_pattern_expr(&mut self, expr : &AssignPattern) -> RuntimeError<Option<Literal>> {
+        let value = self.evaluate(&expr.value)?;
+
+        let mut bindings = Vec::new();
+        self.destructure(&expr.pattern, &value, &expr.keyword, &mut bindings)?;
+
+        for (name, v) in bindings {
+            let mut e = self.environment.clone();
+            e.assign(self, Token::new(TokenType::Identifier, &name, None, expr.keyword.line), v)?;
+            self.environment = e;
+        }
+        Ok(value)
+    }
+    
     fn visit_logical_expr(&mut self, expr : &Logical) -> RuntimeError<Option<Literal>> {
         let left = self.evaluate(&expr.left)?;
         
@@ -1353,6 +2455,11 @@ impl ExprVisitor for Interpreter {
                     }
                 }
             },
+            TokenType::QuestionQuestion => {
+                if left.is_some() {
+                    return Ok(left);
+                }
+            },
             _ => {
                 if let Some(Literal::Boolean(b)) = self.is_truthy(left.clone()) {
                     if !b {
@@ -1367,6 +2474,9 @@ impl ExprVisitor for Interpreter {
     
     fn visit_call_expr(&mut self, expr : &Call) -> RuntimeError<Option<Literal>> {
         let callee = self.evaluate(&expr.callee)?;
+        if self.short_circuit {
+            return Ok(None);
+        }
         
         let callee_token = if let Some(v) = &expr.callee.as_any().downcast_ref::<VarExpr>() {
             v.name.clone()
@@ -1378,7 +2488,21 @@ impl ExprVisitor for Interpreter {
         let mut arguments = Vec::new();
         
         for arg in &expr.arguments {
-            arguments.push(self.evaluate(&arg)?);
+            if let Some(spread) = arg.as_any().downcast_ref::<Spread>() {
+                match self.evaluate(&spread.value)? {
+                    Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) => arguments.extend(c.into_iter().map(|v| *v)),
+                    Some(Literal::Range(r)) => arguments.extend(r.items().into_iter().map(|v| *v)),
+                    _ => return Err(LoxError::type_mismatch(spread.dots.clone(), "Only lists and ranges can be spread into arguments."))
+                }
+            }
+            else {
+                arguments.push(self.evaluate(arg)?);
+            }
+        }
+        
+        let mut named = Vec::new();
+        for (name, value) in &expr.named {
+            named.push((name.clone(), self.evaluate(value)?));
         }
         
         let function_val = match callee.clone() {
@@ -1388,84 +2512,106 @@ impl ExprVisitor for Interpreter {
             Some(Literal::Class(v)) => {
                 Some(v as Box<dyn LoxCallable>)
             },
+            //an instance whose class has __call__ is called like a function
+            Some(Literal::Instance(i)) if i.class.find_method(String::from("__call__")).is_some() => {
+                let method = i.class.find_method(String::from("__call__")).unwrap().bind(self, &i);
+                let arguments = method.arrange(self, &expr.paren, arguments, named)?;
+                return method.call(self, callee_token, arguments, true);
+            },
             _ => {
                 if let Some(v) = expr.callee.as_any().downcast_ref::<VarExpr>() {
-                    return Err((
+                    return Err(LoxError::not_callable(
                         v.name.clone(), 
-                        format!(
-                            "Expected function."
-                        )
+                        "Expected function."
                     ));
                 }
                 else {
-                    return Err((
+                    return Err(LoxError::not_callable(
                         expr.paren.clone(), 
-                        format!(
-                            "Expected variable expression."
-                        )
+                        "Expected variable expression."
                     ));
                 }
             }
         };
         
+        //native callables never leave a frame or a bound this behind, so none of the write-back below applies to them, static methods neither
+        //functions and classes reached through a property, like a module's, are called as they are instead of by name from this scope
+        if let Some(f) = &function_val {
+            let is_static = matches!(f.as_any().downcast_ref::<LoxFunction>(), Some(LoxFunction { f_type : FunctionType::Static, .. }));
+            let is_member = expr.callee.as_any().downcast_ref::<Get>().is_some() && (
+                matches!(f.as_any().downcast_ref::<LoxFunction>(), Some(LoxFunction { f_type : FunctionType::Normal, .. })) ||
+                f.as_any().downcast_ref::<LoxClass>().is_some()
+            );
+            if is_static || is_member || (f.as_any().downcast_ref::<LoxFunction>().is_none() && f.as_any().downcast_ref::<LoxClass>().is_none()) {
+                let arguments = f.arrange(self, &expr.paren, arguments, named)?;
+                return f.call(self, callee_token, arguments, true);
+            }
+        }
+        
+        //super.m() runs on a copy of this and writes back what it changed, looking m up by name would find a global like the parent's own class instead
+        if expr.callee.as_any().is::<Super>() {
+            if let Some(Literal::Function(function)) = callee {
+                let arguments = function.arrange(self, &expr.paren, arguments, named)?;
+                //an initializer hands back this wrapped as a return, which would end the caller's block too
+                let res = match function.call(self, callee_token, arguments, false)? {
+                    Some(Literal::Return(v)) => Some(*v),
+                    v => v
+                };
+                
+                if let Ok(Some(Literal::Instance(inst_old))) = self.environment
+                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
+                    if let Some(prev) = self.environment.clone().enclosing {
+                        self.environment = *prev;
+                    }
+                    
+                    if let Ok(Some(Literal::Instance(inst))) = self.environment
+                    .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
+                        let mut new_inst = inst;
+                        new_inst.fields = inst_old.fields.clone();
+                        let mut e = self.environment.clone();
+
+                        e.assign(
+                            self,
+                            Token::new(TokenType::Identifier, "this", None, 0),
+                            Some(Literal::Instance(new_inst))
+                        )?;
+                        self.environment = e;
+                    }
+                }
+                
+                return Ok(res);
+            }
+            else {
+                return Err(LoxError::not_callable(
+                    expr.paren.clone(), 
+                    "Expected function from super."
+                ));
+            }
+        }
+
         match self.environment.get(
             self,
             function_val.clone().unwrap().get_name()
             ) {
             Ok(Some(Literal::Function(function))) => {
-                if arguments.len() != function.arity() {
-                    Err((
-                        expr.paren.clone(), 
-                        format!(
-                            "Expected {} arguments but got {}.", 
-                            function.arity(), 
-                            arguments.len()
-                        )
-                    ))
-                }
-                else {
-                    Ok(function.call(self, callee_token, arguments, true)?)
-                }
+                let arguments = function.arrange(self, &expr.paren, arguments, named)?;
+                Ok(function.call(self, callee_token, arguments, true)?)
             },
             Ok(Some(Literal::Class(function))) => {
-                if arguments.len() != function.arity() {
-                    Err((
-                        expr.paren.clone(), 
-                        format!(
-                            "Expected {} arguments but got {}.", 
-                            function.arity(), 
-                            arguments.len()
-                        )
-                    ))
-                }
-                else {
-                    Ok(function.call(self, callee_token, arguments, true)?)
-                }
+                let arguments = function.arrange(self, &expr.paren, arguments, named)?;
+                Ok(function.call(self, callee_token, arguments, true)?)
             },
             Err(_) => {
                 if let Some(_) = expr.callee.as_any().downcast_ref::<VarExpr>() {
                     let func = self.evaluate(&expr.callee)?;
                     if let Some(Literal::Function(f)) = func {
-                        if arguments.len() != f.arity() {
-                            Err((
-                                expr.paren.clone(), 
-                                format!(
-                                    "Expected {} arguments but got {}.", 
-                                    f.arity(), 
-                                    arguments.len()
-                                )
-                            ))
-                        }
-                        else {
-                            Ok(f.call(self, callee_token, arguments, true)?)
-                        }
+                        let arguments = f.arrange(self, &expr.paren, arguments, named)?;
+                        Ok(f.call(self, callee_token, arguments, true)?)
                     }
                     else {
-                        Err((
+                        Err(LoxError::not_callable(
                             expr.paren.clone(), 
-                            format!(
-                                "Expected function from var."
-                            )
+                            "Expected function from var."
                         ))
                     }
                 }
@@ -1473,61 +2619,48 @@ impl ExprVisitor for Interpreter {
                     let func = self.evaluate(&expr.callee)?;
                     
                     if let Some(Literal::Function(f)) = func {
-                        if arguments.len() != f.arity() {
-                            Err((
-                                expr.paren.clone(), 
-                                format!(
-                                    "Expected {} arguments but got {}.", 
-                                    f.arity(), 
-                                    arguments.len()
-                                )
-                            ))
-                        }
-                        else {
-                            let res = f.call(self, callee_token, arguments, false)?;
-                            
-                            if let Some(caller) = v.object.as_any().downcast_ref::<VarExpr>() {
-                                if let Ok(Some(v2)) = self.environment
-                                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
-                                    if let Some(prev) = self.environment.clone().enclosing {
-                                        self.environment = *prev;
-                                    }
-                                    
-                                    let mut e = self.environment.clone();
-                                    e.assign(self, caller.name.clone(), Some(v2.clone()))?;
-                                    self.environment = e;
+                        let arguments = f.arrange(self, &expr.paren, arguments, named)?;
+                        let res = f.call(self, callee_token, arguments, false)?;
+                        
+                        if let Some(caller) = v.object.as_any().downcast_ref::<VarExpr>() {
+                            if let Ok(Some(v2)) = self.environment
+                            .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
+                                if let Some(prev) = self.environment.clone().enclosing {
+                                    self.environment = *prev;
                                 }
+                                
+                                let mut e = self.environment.clone();
+                                e.assign(self, caller.name.clone(), Some(v2.clone()))?;
+                                self.environment = e;
                             }
-                            else if let Some(caller) = v.object.as_any().downcast_ref::<IndexGet>() {
-                                if let Ok(Some(v2)) = self.environment
-                                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
-                                    if let Some(prev) = self.environment.clone().enclosing {
-                                        self.environment = *prev;
-                                    }
-                                    
-                                    if let Some(variable) = caller.object.as_any().downcast_ref::<VarExpr>() {
-                                        if let Some(Literal::Collection(mut coll)) = self.environment.get(self, variable.name.clone())? {
-                                            if let Some(Literal::Number(index)) = self.evaluate(&caller.index)? {
-                                                let len = coll.len() as i32;
-                                                coll[((index as i32).rem_euclid(len)) as usize] = Box::new(Some(v2.clone()));
-                                                let mut e = self.environment.clone();
-                                                e.assign(self, variable.name.clone(), Some(Literal::Collection(coll.clone())))?;
-                                                self.environment = e;
-                                            }
+                        }
+                        else if let Some(caller) = v.object.as_any().downcast_ref::<IndexGet>() {
+                            if let Ok(Some(v2)) = self.environment
+                            .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
+                                if let Some(prev) = self.environment.clone().enclosing {
+                                    self.environment = *prev;
+                                }
+                                
+                                if let Some(variable) = caller.object.as_any().downcast_ref::<VarExpr>() {
+                                    if let Some(Literal::Collection(mut coll)) = self.environment.get(self, variable.name.clone())? {
+                                        if let Some(Literal::Number(index)) = self.evaluate(&caller.index)? {
+                                            let len = coll.len() as i32;
+                                            coll[((index as i32).rem_euclid(len)) as usize] = Box::new(Some(v2.clone()));
+                                            let mut e = self.environment.clone();
+                                            e.assign(self, variable.name.clone(), Some(Literal::Collection(coll.clone())))?;
+                                            self.environment = e;
                                         }
                                     }
                                 }
                             }
-                            
-                            Ok(res)
                         }
+                        
+                        Ok(res)
                     }
                     else {
-                        Err((
+                        Err(LoxError::not_callable(
                             expr.paren.clone(), 
-                            format!(
-                                "Expected function from get."
-                            )
+                            "Expected function from get."
                         ))
                     }
                 }
@@ -1535,127 +2668,61 @@ impl ExprVisitor for Interpreter {
                     let func = self.evaluate(&expr.callee)?;
                     
                     if let Some(Literal::Function(f)) = func {
-                        if arguments.len() != f.arity() {
-                            Err((
-                                expr.paren.clone(), 
-                                format!(
-                                    "Expected {} arguments but got {}.", 
-                                    f.arity(), 
-                                    arguments.len()
-                                )
-                            ))
-                        }
-                        else {
-                            let res = f.call(self, callee_token, arguments, true)?;
-                            
-                            if let Some(caller) = v.object.as_any().downcast_ref::<VarExpr>() {
-                                if let Ok(Some(v)) = self.environment
-                                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
-                                    if let Some(prev) = self.environment.clone().enclosing {
-                                        self.environment = *prev;
-                                    }
-                                    let mut e = self.environment.clone();
-                                    e.assign(self, caller.name.clone(), Some(v.clone()))?;
-                                    self.environment = e;
-                                }
-                            }
-                            else if let Some(caller) = v.object.as_any().downcast_ref::<IndexGet>() {
-                                if let Ok(Some(v2)) = self.environment
-                                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
-                                    if let Some(prev) = self.environment.clone().enclosing {
-                                        self.environment = *prev;
-                                    }
-                                    
-                                    if let Some(variable) = caller.object.as_any().downcast_ref::<VarExpr>() {
-                                        if let Some(Literal::Collection(mut coll)) = self.environment.get(self, variable.name.clone())? {
-                                            if let Some(Literal::Number(index)) = self.evaluate(&caller.index)? {
-                                                let len = coll.len() as i32;
-                                                coll[((index as i32).rem_euclid(len)) as usize] = Box::new(Some(v2.clone()));
-                                                let mut e = self.environment.clone();
-                                                e.assign(self, variable.name.clone(), Some(Literal::Collection(coll.clone())))?;
-                                                self.environment = e;
-                                            }
-                                        }
-                                    }
+                        let arguments = f.arrange(self, &expr.paren, arguments, named)?;
+                        let res = f.call(self, callee_token, arguments, true)?;
+                        
+                        if let Some(caller) = v.object.as_any().downcast_ref::<VarExpr>() {
+                            if let Ok(Some(v)) = self.environment
+                            .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
+                                if let Some(prev) = self.environment.clone().enclosing {
+                                    self.environment = *prev;
                                 }
+                                let mut e = self.environment.clone();
+                                e.assign(self, caller.name.clone(), Some(v.clone()))?;
+                                self.environment = e;
                             }
-                            
-                            Ok(res)
-                        }
-                    }
-                    else {
-                        Err((
-                            expr.paren.clone(), 
-                            format!(
-                                "Expected function from get."
-                            )
-                        ))
-                    }
-                }
-                else if let Some(_) = expr.callee.as_any().downcast_ref::<Super>() {
-                    let funct = self.evaluate(&expr.callee.clone())?;
-                    if let Some(Literal::Function(function)) = funct {
-                        if arguments.len() != function.arity() {
-                            Err((
-                                expr.paren.clone(), 
-                                format!(
-                                    "Expected {} arguments but got {}.", 
-                                    function.arity(), 
-                                    arguments.len()
-                                )
-                            ))
                         }
-                        else {
-                            let res = function.call(self, callee_token, arguments, false)?;
-                            
-                            if let Ok(Some(Literal::Instance(inst_old))) = self.environment
+                        else if let Some(caller) = v.object.as_any().downcast_ref::<IndexGet>() {
+                            if let Ok(Some(v2)) = self.environment
                             .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
                                 if let Some(prev) = self.environment.clone().enclosing {
-                                        self.environment = *prev;
+                                    self.environment = *prev;
+                                }
+                                
+                                if let Some(variable) = caller.object.as_any().downcast_ref::<VarExpr>() {
+                                    if let Some(Literal::Collection(mut coll)) = self.environment.get(self, variable.name.clone())? {
+                                        if let Some(Literal::Number(index)) = self.evaluate(&caller.index)? {
+                                            let len = coll.len() as i32;
+                                            coll[((index as i32).rem_euclid(len)) as usize] = Box::new(Some(v2.clone()));
+                                            let mut e = self.environment.clone();
+                                            e.assign(self, variable.name.clone(), Some(Literal::Collection(coll.clone())))?;
+                                            self.environment = e;
+                                        }
                                     }
-                                    
-                                if let Ok(Some(Literal::Instance(inst))) = self.environment
-                                .get(self, Token::new(TokenType::Identifier, "this", None, 0)) {
-                                    let mut new_inst = inst;
-                                    new_inst.fields = inst_old.fields.clone();
-                                    let mut e = self.environment.clone();
-
-                                    e.assign(
-                                        self,
-                                        Token::new(TokenType::Identifier, "this", None, 0),
-                                        Some(Literal::Instance(new_inst))
-                                    )?;
-                                    self.environment = e;
                                 }
                             }
-                            
-                            Ok(res)
                         }
+                        
+                        Ok(res)
                     }
                     else {
-                        Err((
+                        Err(LoxError::not_callable(
                             expr.paren.clone(), 
-                            format!(
-                                "Expected function from super."
-                            )
+                            "Expected function from get."
                         ))
                     }
                 }
                 else {
-                    Err((
+                    Err(LoxError::not_callable(
                         expr.paren.clone(), 
-                        format!(
-                            "Expected function from unknown."
-                        )
+                        "Expected function from unknown."
                     ))
                 }
             },
             _ => {
-                Err((
+                Err(LoxError::not_callable(
                     expr.paren.clone(), 
-                    format!(
-                        "Expected function nil."
-                    )
+                    "Expected function nil."
                 ))
             }
         }
@@ -1663,12 +2730,39 @@ impl ExprVisitor for Interpreter {
 
     fn visit_get_expr(&mut self, expr : &Get) -> RuntimeError<Option<Literal>> {
         let object = self.evaluate(&expr.object.clone())?;
+        if self.short_circuit {
+            return Ok(None);
+        }
         
         if let Some(Literal::Instance(v)) = object {
             Ok(v.get(expr.name.clone(), self)?)
         }
+        else if let Some(Literal::Class(c)) = object {
+            c.get_static(expr.name.clone(), self)
+        }
+        else if let Some(Literal::Enum(e)) = object {
+            match e.variant(&expr.name.lexeme) {
+                Some(v) if v.fields.is_empty() => Ok(Some(Literal::Variant(Box::new(v)))),
+                Some(v) => Ok(Some(Literal::Function(Box::new(VariantConstructor::new(v))))),
+                None if expr.name.lexeme == "values" => Ok(Some(Literal::Function(Box::new(EnumValues::new(*e))))),
+                None => Err(LoxError::undefined_property(expr.name.clone(), &format!("Enum '{}' has no variant '{}'.", e.name, expr.name.lexeme)))
+            }
+        }
+        else if let Some(Literal::Module(m)) = object {
+            m.get(&expr.name, self)
+        }
+        else if let Some(Literal::Variant(v)) = object {
+            match v.get(&expr.name.lexeme) {
+                Some(value) => Ok(value),
+                None => Err(LoxError::undefined_property(expr.name.clone(), &format!("{}.{} has no value named '{}'.", v.enum_name, v.name, expr.name.lexeme)))
+            }
+        }
+        else if object.is_none() && expr.optional {
+            self.short_circuit = true;
+            Ok(None)
+        }
         else {
-            Err((expr.name.clone(), "Only instances have properties.".to_string()))
+            Err(LoxError::type_mismatch(expr.name.clone(), "Only instances have properties."))
         }
     }
     
@@ -1676,13 +2770,15 @@ impl ExprVisitor for Interpreter {
         let object = self.evaluate(&expr.object)?;
         
         if let Some(Literal::Instance(mut v)) = object {
-            let value = self.evaluate(&expr.value)?;
-
             let current_val = v.get(expr.name.clone(), self);
+            if let (Ok(Some(current)), Some(TokenType::QuestionQuestion)) = (&current_val, expr.assign_type) {
+                return Ok(Some(current.clone()));
+            }
+            let value = self.evaluate(&expr.value)?;
             
-            let new_value = Self::assign_helper(current_val.clone(), value.clone(), expr.assign_type.clone(), expr.name.clone())?;
+            let new_value = self.assign_value(current_val.clone(), value.clone(), expr.assign_type, expr.name.clone())?;
             
-            v.set(expr.name.clone(), new_value.clone(), self);
+            v.set(expr.name.clone(), new_value.clone(), self)?;
             
             if let Some(as_this) = expr.object.as_any().downcast_ref::<This>() {
                 let mut e = self.environment.clone();
@@ -1697,8 +2793,19 @@ impl ExprVisitor for Interpreter {
 
             Ok(new_value)
         }
+        else if let Some(Literal::Class(c)) = object {
+            let current_val = c.get_static(expr.name.clone(), self);
+            if let (Ok(Some(current)), Some(TokenType::QuestionQuestion)) = (&current_val, expr.assign_type) {
+                return Ok(Some(current.clone()));
+            }
+            let value = self.evaluate(&expr.value)?;
+            let new_value = self.assign_value(current_val, value, expr.assign_type, expr.name.clone())?;
+            
+            c.set_static(expr.name.clone(), new_value.clone(), self)?;
+            Ok(new_value)
+        }
         else {
-            Err((expr.name.clone(), "Only instances have fields.".to_string()))
+            Err(LoxError::type_mismatch(expr.name.clone(), "Only instances have fields."))
         }
     }
     
@@ -1711,132 +2818,123 @@ impl ExprVisitor for Interpreter {
     }
     
     fn visit_index_get_expr(&mut self, expr : &IndexGet) -> RuntimeError<Option<Literal>> {
-        if let Some(v) = expr.object.as_any().downcast_ref::<IndexGet>() {
-            if let Some(Literal::Collection(c)) = self.evaluate(&expr.object)? {
-                if let Some(Literal::Number(index)) = self.evaluate(&expr.index)? {
-                    if c.len() > 0 {
-                        if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
-                            if let Some(address) = i.address {
-                                if let Some(a) = self.references.get(address) {
-                                    Ok(a.clone())
-                                }       
-                                else {
-                                    Err((expr.keyword.clone(), format!("Undefined reference.")))
-                                }
-                            }  
-                            else {
-                                Err((expr.keyword.clone(), format!("Undefined reference.")))
-                            }                       
-                        }
-                        else {
-                            Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
-                        }
-                    }
-                    else {
-                        Err((expr.keyword.clone(), "Attempt to index empty collection.".to_string()))
-                    }
-                }
-                else if let Some(Literal::StrongNumber(index)) = self.evaluate(&expr.index)? {
-                    if c.len() > 0 {
-                        if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
-                            if let Some(address) = i.address {
-                                if let Some(a) = self.references.get(address) {
-                                    Ok(a.clone())
-                                }       
-                                else {
-                                    Err((expr.keyword.clone(), format!("Undefined reference.")))
-                                }
-                            }  
-                            else {
-                                Err((expr.keyword.clone(), format!("Undefined reference.")))
-                            }                       
-                        }
-                        else {
-                            Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
-                        }
-                    }
-                    else {
-                        Err((expr.keyword.clone(), "Attempt to index empty collection.".to_string()))
-                    }
-                }
-                else {
-                     Err((expr.keyword.clone(), "Attempt to index with non list type.".to_string()))
-                }
-            }
-            else {
-                self.visit_index_get_expr(&v)
+        let mut object = self.evaluate(&expr.object)?;
+        if self.short_circuit {
+            return Ok(None);
+        }
+        if object.is_none() && expr.optional {
+            self.short_circuit = true;
+            return Ok(None);
+        }
+        if let Some(slice) = expr.index.as_any().downcast_ref::<Slice>() {
+            return self.slice(object, &Selection::Slice(slice), &expr.keyword);
+        }
+        let key = self.evaluate(&expr.index)?;
+        if let Some(Literal::Instance(i)) = &object {
+            if let Some(v) = self.special_method(i, "__index__", vec![key.clone()], &expr.keyword)? {
+                return Ok(v);
             }
         }
-        else {
-            let mut object = self.evaluate(&expr.object)?;
-            if let Some(Literal::String(s)) = object {
-                let mut vec = vec!();
-                for c in s.chars() {
-                    vec.push(Box::new(Some(Literal::String(String::from(c)))));
-                }
-                object = Some(Literal::Collection(vec));
+        if let Some(Literal::Map(m)) = &object {
+            return self.map_get(m, &key, &expr.keyword);
+        }
+        if let Some(Literal::Range(r)) = key {
+            return self.slice(object, &Selection::Range(r, &expr.keyword), &expr.keyword);
+        }
+        if let Some(Literal::Range(r)) = object {
+            return match key {
+                Some(Literal::Number(index)) | Some(Literal::StrongNumber(index)) if !r.is_empty() => {
+                    Ok(Some(Literal::Number(r.nth((index as i64).rem_euclid(r.len() as i64) as usize))))
+                },
+                Some(Literal::Number(_)) | Some(Literal::StrongNumber(_)) => {
+                    Err(LoxError::index_out_of_range(expr.keyword.clone(), "Attempt to index empty range."))
+                },
+                _ => Err(LoxError::type_mismatch(expr.keyword.clone(), "Attempt to index with non number type."))
+            };
+        }
+
+        if let Some(Literal::String(s)) = object {
+            let mut vec = vec!();
+            for c in s.chars() {
+                vec.push(Box::new(Some(Literal::String(String::from(c)))));
             }
-            
-            if let Some(Literal::Collection(c)) =  object {
-                if let Some(Literal::Number(index)) = self.evaluate(&expr.index)? {
-                    if c.len() > 0 {
-                        if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
-                            if let Some(address) = i.address {
-                                if let Some(a) = self.references.get(address) {
-                                    Ok(a.clone())
-                                }       
-                                else {
-                                    Err((expr.keyword.clone(), format!("Undefined reference.")))
-                                }
-                            }  
+            object = Some(Literal::Collection(vec));
+        }
+        
+        if let Some(Literal::Collection(c)) =  object {
+            if let Some(Literal::Number(index)) = key {
+                if c.len() > 0 {
+                    if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
+                        if let Some(address) = i.address {
+                            if let Some(a) = self.references.get(address) {
+                                Ok(a.clone())
+                            }       
                             else {
-                                Err((expr.keyword.clone(), format!("Undefined reference.")))
-                            }                       
-                        }
+                                Err(LoxError::undefined_variable(expr.keyword.clone(), "Undefined reference."))
+                            }
+                        }  
                         else {
-                            Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
-                        }
+                            Err(LoxError::undefined_variable(expr.keyword.clone(), "Undefined reference."))
+                        }                       
                     }
                     else {
-                        Err((expr.keyword.clone(), "Attempt to index empty collection.".to_string()))
+                        Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
                     }
                 }
-                else if let Some(Literal::StrongNumber(index)) = self.evaluate(&expr.index)? {
-                    if c.len() > 0 {
-                        if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
-                            if let Some(address) = i.address {
-                                if let Some(a) = self.references.get(address) {
-                                    Ok(a.clone())
-                                }       
-                                else {
-                                    Err((expr.keyword.clone(), format!("Undefined reference.")))
-                                }
-                            }  
+                else {
+                    Err(LoxError::index_out_of_range(expr.keyword.clone(), "Attempt to index empty collection."))
+                }
+            }
+            else if let Some(Literal::StrongNumber(index)) = key {
+                if c.len() > 0 {
+                    if let Some(Literal::Instance(i)) = *c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone() {
+                        if let Some(address) = i.address {
+                            if let Some(a) = self.references.get(address) {
+                                Ok(a.clone())
+                            }       
                             else {
-                                Err((expr.keyword.clone(), format!("Undefined reference.")))
-                            }                       
-                        }
+                                Err(LoxError::undefined_variable(expr.keyword.clone(), "Undefined reference."))
+                            }
+                        }  
                         else {
-                            Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
-                        }
+                            Err(LoxError::undefined_variable(expr.keyword.clone(), "Undefined reference."))
+                        }                       
                     }
                     else {
-                        Err((expr.keyword.clone(), "Attempt to index empty collection.".to_string()))
+                        Ok(*c[((index as i32).rem_euclid(c.len() as i32)) as usize].clone())
                     }
                 }
                 else {
-                     Err((expr.keyword.clone(), "Attempt to index with non collection type.".to_string()))
+                    Err(LoxError::index_out_of_range(expr.keyword.clone(), "Attempt to index empty collection."))
                 }
             }
             else {
-                Err((expr.keyword.clone(), "Attempt to index non-var.".to_string()))
+                 Err(LoxError::type_mismatch(expr.keyword.clone(), "Attempt to index with non collection type."))
             }
         }
+        else {
+            Err(LoxError::type_mismatch(expr.keyword.clone(), "Attempt to index non-var."))
+        }
     }
     
     //added
     fn visit_index_set_expr(&mut self, expr : &IndexSet, coll : Vec<Box<Option<Literal>>>) -> RuntimeError<Option<Literal>> {
+        if let Some(slice) = expr.index.as_any().downcast_ref::<Slice>() {
+            return self.assign_slice(expr, slice);
+        }
+        if expr.assign_type == Some(TokenType::QuestionQuestion) {
+            return self.assign_entry_if_nil(expr, coll);
+        }
+
         let mut object = self.evaluate(&expr.object)?;
+        if let Some(Literal::Map(m)) = object {
+            return self.assign_map_entry(expr, m, coll);
+        }
+        if let Some(Literal::Instance(i)) = &object {
+            if i.class.find_method(String::from("__setindex__")).is_some() {
+                return self.assign_instance_entry(expr, i, coll);
+            }
+        }
         let mut string_manip = false;
         if let Some(Literal::String(s)) = object {
             let mut vec = vec!();
@@ -1858,7 +2956,7 @@ impl ExprVisitor for Interpreter {
                     Ok(*v[(index as usize).rem_euclid(len)].clone())
                 }
                 else {
-                    Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                    Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                 }
             }
             else if let Some(Literal::StrongNumber(index)) = self.evaluate(&expr.index)? {
@@ -1866,14 +2964,14 @@ impl ExprVisitor for Interpreter {
                     Ok(*v[(index as usize).rem_euclid(len)].clone())
                 }
                 else {
-                    Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                    Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                 }
             }
             else {
-                Err((expr.name.clone(), "Attempt to index with non number type.".to_string()))
+                Err(LoxError::type_mismatch(expr.name.clone(), "Attempt to index with non number type."))
             };
 
-            let new_value = Self::assign_helper(current_val.clone(), value.clone(), expr.assign_type.clone(), expr.name.clone())?;
+            let new_value = self.assign_value(current_val.clone(), value.clone(), expr.assign_type, expr.name.clone())?;
         
             if let Some(as_var) = expr.object.as_any().downcast_ref::<VarExpr>() {
                 if let Some(Literal::Number(index)) = self.evaluate(&expr.index)? {
@@ -1882,7 +2980,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(Some(Literal::Collection(coll.clone())));
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                     else {
@@ -1890,7 +2988,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(new_value.clone());
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                 }
@@ -1900,7 +2998,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(Some(Literal::Collection(coll.clone())));
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                     else {
@@ -1908,12 +3006,12 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(new_value.clone());
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                 }
                 else {
-                    return Err((expr.name.clone(), "Attempt to index with non number type.".to_string()));
+                    return Err(LoxError::type_mismatch(expr.name.clone(), "Attempt to index with non number type."));
                 }
                 if !string_manip {
                     let mut e = self.environment.clone();
@@ -1944,7 +3042,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(Some(Literal::Collection(coll.clone())));
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                     else {
@@ -1952,7 +3050,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(new_value.clone());
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                 }
@@ -1962,7 +3060,7 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(Some(Literal::Collection(coll.clone())));
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                     else {
@@ -1970,16 +3068,16 @@ impl ExprVisitor for Interpreter {
                             v[(index as usize).rem_euclid(len)] = Box::new(new_value.clone());
                         }
                         else {
-                            return Err((expr.name.clone(), "Attempt to index empty collection.".to_string()))
+                            return Err(LoxError::index_out_of_range(expr.name.clone(), "Attempt to index empty collection."))
                         }
                     }
                 }
                 else {
-                    return Err((expr.name.clone(), "Attempt to index with non number type.".to_string()));
+                    return Err(LoxError::type_mismatch(expr.name.clone(), "Attempt to index with non number type."));
                 }
                 
                 if let Some(Literal::Instance(mut inst)) = self.evaluate(&as_get.object)? {
-                    inst.set(as_get.name.clone(), Some(Literal::Collection(v.clone())), self);
+                    inst.set(as_get.name.clone(), Some(Literal::Collection(v.clone())), self)?;
 
                     if let Some(as_this) = as_get.object.as_any().downcast_ref::<This>() {
                         let mut e = self.environment.clone();
@@ -2009,7 +3107,7 @@ impl ExprVisitor for Interpreter {
                         v[(index as usize).rem_euclid(len)] = Box::new(new_value.clone());
                     }
                     else {
-                        return Err((expr.name.clone(), "Attempt to index with non number type.".to_string()));
+                        return Err(LoxError::type_mismatch(expr.name.clone(), "Attempt to index with non number type."));
                     }
                     
                     self.visit_index_set_expr(&new_set, v.clone())
@@ -2022,18 +3120,89 @@ impl ExprVisitor for Interpreter {
                         v[(index as usize).rem_euclid(len)] = Box::new(Some(Literal::Collection(coll.clone())));
                     }
                     else {
-                        return Err((expr.name.clone(), "Attempt to index with non number type.".to_string()));
+                        return Err(LoxError::type_mismatch(expr.name.clone(), "Attempt to index with non number type."));
                     }
                     
                     self.visit_index_set_expr(&new_set, v.clone())
                 }
             }
             else {
-                Err((expr.name.clone(), "Only list types can be indexed.".to_string()))
+                Err(LoxError::type_mismatch(expr.name.clone(), "Only list types can be indexed."))
             }
         }
         else {
-            Err((expr.name.clone(), "Only list types can be indexed.".to_string()))
+            Err(LoxError::type_mismatch(expr.name.clone(), "Only list types can be indexed."))
+        }
+    }
+
+    fn visit_range_expr(&mut self, expr : &RangeExpr) -> RuntimeError<Option<Literal>> {
+        let mut bound = |value : &Box<dyn Expr>| -> RuntimeError<f64> {
+            match self.evaluate(value)? {
+                Some(Literal::Number(x)) | Some(Literal::StrongNumber(x)) => Ok(x),
+                _ => Err(LoxError::type_mismatch(expr.operator.clone(), "Range bounds must be numbers."))
+            }
+        };
+        
+        let start = bound(&expr.start)?;
+        let end = bound(&expr.end)?;
+        let step = match &expr.step {
+            Some(v) => bound(v)?,
+            None => 1.0
+        };
+        if step == 0.0 {
+            return Err(LoxError::type_mismatch(expr.operator.clone(), "Range step cannot be zero."));
+        }
+        Ok(Some(Literal::Range(LoxRange::new(start, end, step, expr.operator.type_ == TokenType::DotDotEqual))))
+    }
+
+    fn visit_spread_expr(&mut self, expr : &Spread) -> RuntimeError<Option<Literal>> {
+        Err(LoxError::syntax(expr.dots.clone(), "'...' can only be used on call arguments."))
+    }
+
+    fn visit_optional_chain_expr(&mut self, expr : &OptionalChain) -> RuntimeError<Option<Literal>> {
+        let value = self.evaluate(&expr.expression);
+        self.short_circuit = false;
+        value
+    }
+
+    fn visit_slice_expr(&mut self, expr : &Slice) -> RuntimeError<Option<Literal>> {
+        Err(LoxError::syntax(expr.colon.clone(), "Slices can only be used inside '[]'."))
+    }
+
+    fn visit_map_expr(&mut self, expr : &MapLiteral) -> RuntimeError<Option<Literal>> {
+        let mut map = LoxMap::new();
+        for (key, value) in &expr.entries {
+            let k = self.evaluate(key)?;
+            let hashed = self.require_key(&k, &expr.brace)?;
+            let v = self.evaluate(value)?;
+            if let Some(k) = k {
+                map.insert(hashed, k, v);
+            }
+        }
+        Ok(Some(Literal::Map(map)))
+    }
+
+    fn visit_iterable_expr(&mut self, expr : &Iterable) -> RuntimeError<Option<Literal>> {
+        let pair = |a : Option<Literal>, b : Option<Literal>| Box::new(Some(Literal::Collection(vec!(Box::new(a), Box::new(b)))));
+        
+        match self.evaluate(&expr.value)? {
+            Some(Literal::Map(m)) => {
+                let items = m.entries().iter().map(|(k, v)| match expr.pairs {
+                    true => pair(Some(k.clone()), v.clone()),
+                    false => Box::new(Some(k.clone()))
+                }).collect();
+                Ok(Some(Literal::Collection(items)))
+            },
+            Some(Literal::Collection(c)) | Some(Literal::StrongCollection(c)) if expr.pairs => {
+                let items = c.into_iter().enumerate().map(|(i, v)| pair(Some(Literal::Number(i as f64)), *v)).collect();
+                Ok(Some(Literal::Collection(items)))
+            },
+            Some(Literal::Range(r)) if expr.pairs => {
+                let items = r.items().into_iter().enumerate().map(|(i, v)| pair(Some(Literal::Number(i as f64)), *v)).collect();
+                Ok(Some(Literal::Collection(items)))
+            },
+            _ if expr.pairs => Err(LoxError::type_mismatch(expr.keyword.clone(), "Only maps and lists can be looped over in pairs.")),
+            v => Ok(v)
         }
     }
 }
\ No newline at end of file
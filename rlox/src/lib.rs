@@ -1,10 +1,13 @@
 pub mod tokens;
+pub mod errors;
 pub mod types;
 pub mod interpreter;
 pub mod environment;
 pub mod statements;
 pub mod expressions;
 #[macro_use] pub mod std_lib;
+pub mod diagnostics;
+pub mod highlight;
 pub mod scanner;
 pub mod parser;
 pub mod application;
@@ -13,6 +16,11 @@ pub mod application;
 mod tests {
     use crate::application::App;
     use crate::types::Literal;
+    use crate::errors::LoxError;
+    use crate::statements::{Class, Function, Var};
+    use crate::scanner::Scanner;
+    use crate::highlight::{to_ansi, to_html};
+    use crate::tokens::{Token, TokenType};
 
     #[test]
     fn conditionals() {
@@ -188,4 +196,947 @@ mod tests {
         assert_eq!(d.iter().sum::<f64>(), 21.0);
         assert_eq!(e, true);
     }
+
+    #[test]
+    fn error_spans() {
+        let mut lox = App::new();
+        let err = lox.try_run("
+        var a = 1;
+        var b = a - \"x\";
+        ").expect_err("Expected runtime error");
+
+        assert_eq!(err.span().line, 3);
+        assert_eq!(err.span().column, 19);
+
+        let rendered = lox.render_error(&err);
+        assert!(rendered.contains("--> script:3:19"));
+        assert!(rendered.contains("        var b = a - \"x\";"));
+        assert!(rendered.contains("                  ^"));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let mut lox = App::new();
+        let diagnostics = lox.check("script", "
+        var a = (1 + 2;
+        var b = a +;
+        var c = 3 $ 4;
+        num d = 4;
+        ");
+
+        let lines : Vec<i32> = diagnostics.errors.iter().map(|e| e.token().line).collect();
+        assert_eq!(lines, vec!(2, 3, 4, 4));
+
+        for source in ["var x = foo(1 2 3 4);", "bar(\"a\" \"b\" \"c\");"] {
+            assert_eq!(lox.check("script", source).errors.len(), 1);
+        }
+        assert!(lox.check("script", "var f => |a| { return a; };\n;").errors.is_empty());
+
+        let err = lox.try_run("
+        num ran = 1;
+        var broken = ;
+        ").expect_err("Expected syntax error");
+
+        assert_eq!(err.message(), "Expect expression.");
+        assert!(lox.get_value::<f64>("ran").is_err());
+    }
+
+    #[test]
+    fn error_kinds() {
+        let err = App::new().try_run("println(missing);").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::UndefinedVariable(_)));
+
+        let err = App::new().try_run("fn f(a) { return a; } f(1, 2);").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::Arity(_)));
+        assert_eq!(err.message(), "Expected 1 arguments but got 2.");
+
+        let err = App::new().try_run("var a = true - 1;").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+        assert_eq!(err.to_string(), "[line 1 ] TypeMismatch error at '-': Operands must be Numbers.");
+
+        let err = App::new().try_run("var a = 1 +;").expect_err("Expected syntax error");
+        assert!(matches!(err, LoxError::Syntax(_)));
+    }
+
+    #[test]
+    fn tracebacks() {
+        let mut lox = App::new();
+        let err = lox.try_run(r#"
+            class Shape {
+                area() {
+                    return this.scale(2);
+                }
+                scale(n) {
+                    return n - "x";
+                }
+            }
+
+            fn measure(s) {
+                return s.area();
+            }
+
+            measure(Shape());
+        "#).expect_err("Expected runtime error");
+
+        let frames : Vec<(String, Option<String>, i32)> = lox.traceback().into_iter()
+            .map(|f| (f.function, f.class, f.line))
+            .collect();
+        assert_eq!(frames, vec![
+            ("measure".to_string(), None, 15),
+            ("area".to_string(), Some("Shape".to_string()), 12),
+            ("scale".to_string(), Some("Shape".to_string()), 4),
+        ]);
+
+        let rendered = lox.render_error(&err);
+        assert!(rendered.contains("traceback (most recent call first):"));
+        assert!(rendered.contains("  at Shape.scale (called from line 4)"));
+
+        let mut lox = App::new();
+        lox.try_run("var a = 1;").expect("Expected no error");
+        assert!(lox.traceback().is_empty());
+    }
+
+    #[test]
+    fn string_escapes() {
+        let mut lox = App::new();
+        lox.run(r#"
+            class User {
+                User(name) {
+                    this.name = name;
+                }
+            }
+            var user = User("ann");
+            var items = [1, 2, 3];
+            var a = "hello ${user.name}, you have ${#items} items";
+            var b = "tab\tquote\"slash\\dollar\${x}";
+            var c = "\u{48}\u{1F600}";
+            var d = "outer ${ "inner ${items[0] + 1}" } ${nil}";
+        "#);
+
+        assert_eq!(lox.get_value::<String>("a"), Ok("hello ann, you have 3 items".to_string()));
+        assert_eq!(lox.get_value::<String>("b"), Ok("tab\tquote\"slash\\dollar${x}".to_string()));
+        assert_eq!(lox.get_value::<String>("c"), Ok("H\u{1F600}".to_string()));
+        assert_eq!(lox.get_value::<String>("d"), Ok("outer inner 2 nil".to_string()));
+
+        let diagnostics = App::new().check("script", "var a = \"bad \\q\";\nvar b = \"${1\";");
+        let errors : Vec<(i32, usize, String)> = diagnostics.errors.iter()
+            .map(|e| (e.token().line, e.span().column, e.message().to_string()))
+            .collect();
+        assert_eq!(errors[0], (1, 14, "Invalid escape sequence '\\q'.".to_string()));
+        assert_eq!(errors[1], (2, 10, "Unterminated string interpolation.".to_string()));
+    }
+
+    #[test]
+    fn number_literals() {
+        let mut lox = App::new();
+        lox.run("
+            var hex = 0xFF;
+            var bin = 0b1010;
+            var oct = 0o755;
+            var big = 1_000_000;
+            var small = 1.5e-3;
+            var exp = 2E10;
+            var mask = 0xF0 + 0b1111;
+        ");
+
+        assert_eq!(lox.get_value::<f64>("hex"), Ok(255.0));
+        assert_eq!(lox.get_value::<f64>("bin"), Ok(10.0));
+        assert_eq!(lox.get_value::<f64>("oct"), Ok(493.0));
+        assert_eq!(lox.get_value::<f64>("big"), Ok(1000000.0));
+        assert_eq!(lox.get_value::<f64>("small"), Ok(0.0015));
+        assert_eq!(lox.get_value::<f64>("exp"), Ok(20000000000.0));
+        assert_eq!(lox.get_value::<f64>("mask"), Ok(255.0));
+
+        let diagnostics = App::new().check("script", "var a = 0x;\nvar b = 0b102;\nvar c = 1e;\nvar d = 1_;\nvar e = 12abc;");
+        let errors : Vec<(i32, &str)> = diagnostics.errors.iter()
+            .map(|e| (e.token().line, e.message()))
+            .collect();
+        assert_eq!(errors, vec![
+            (1, "Expect hex digits after '0x'."),
+            (2, "Invalid digit in binary literal."),
+            (3, "Expect digits in exponent."),
+            (4, "Digit separator '_' must be between digits."),
+            (5, "Invalid suffix on number literal."),
+        ]);
+    }
+
+    #[test]
+    fn doc_comments() {
+        let mut lox = App::new();
+        lox.run("
+            /* outer /* nested */
+               still a comment */
+            /// Adds two numbers.
+            /// Returns their sum.
+            fn add(a, b) {
+                return a + b;
+            }
+            var sum = add(1, /* inline */ 2);
+            var add_help = help(add);
+            var stack_help = help(Stack);
+        ");
+
+        assert_eq!(lox.get_value::<f64>("sum"), Ok(3.0));
+        assert_eq!(lox.get_value::<String>("add_help"), Ok("fn add(a, b)\n    Adds two numbers.\n    Returns their sum.\n".to_string()));
+        let stack_help = lox.get_value::<String>("stack_help").unwrap();
+        assert!(stack_help.starts_with("class Stack\n    Last in, first out collection.\n"));
+        assert!(stack_help.contains("  push(item)\n      Puts item on top of the stack.\n"));
+
+        let statements = App::new().compile("script", "/// A counter.\nclass Counter {\n    /// Current count.\n    get() { return 0; }\n}\n/// The answer.\nvar answer = 42;").unwrap();
+        let class = statements[0].as_any().downcast_ref::<Class>().unwrap();
+        assert_eq!(class.doc, Some("A counter.".to_string()));
+        let method = class.methods[0].as_any().downcast_ref::<Function>().unwrap();
+        assert_eq!(method.doc, Some("Current count.".to_string()));
+        let var = statements[1].as_any().downcast_ref::<Var>().unwrap();
+        assert_eq!(var.doc, Some("The answer.".to_string()));
+
+        let diagnostics = App::new().check("script", "var a = 1;\n/* never closed /* */");
+        assert_eq!(diagnostics.errors[0].message(), "Unterminated block comment.");
+        assert_eq!(diagnostics.errors[0].token().line, 2);
+    }
+
+    #[test]
+    fn unicode_source() {
+        let mut lox = App::new();
+        lox.run("
+            var café = \"naïve 😀\";
+            var 変数 = 42;
+            var joined = café + \" \" + (変数 as string);
+        ");
+
+        assert_eq!(lox.get_value::<String>("café"), Ok("naïve 😀".to_string()));
+        assert_eq!(lox.get_value::<String>("joined"), Ok("naïve 😀 42".to_string()));
+
+        let err = App::new().try_run("var é = \"😀\" - 1;").expect_err("Expected runtime error");
+        assert_eq!(err.span().column, 13);
+
+        let line = "var ünï = \"😀 text\" + (12 as string); // é\n";
+        let mut scanner = Scanner::new(line.repeat(20000));
+        assert!(scanner.scan_tokens().is_ok());
+        assert_eq!(scanner.tokens.len(), 20000*11 + 1);
+        assert_eq!(scanner.tokens.last().unwrap().line, 20001);
+    }
+
+    #[test]
+    fn raw_and_multiline_strings() {
+        let mut lox = App::new();
+        lox.run(r##"
+            var path = r"C:\temp\${name}";
+            var quoted = r#"say "hi""#;
+            var query = """
+                SELECT *
+                  FROM users
+                WHERE id = 1;\tend
+                """;
+            var single = """one "quoted" line""";
+            var r = [1, 2];
+            var count = #r;
+        "##);
+
+        assert_eq!(lox.get_value::<String>("path"), Ok("C:\\temp\\${name}".to_string()));
+        assert_eq!(lox.get_value::<String>("quoted"), Ok("say \"hi\"".to_string()));
+        assert_eq!(lox.get_value::<String>("query"), Ok("SELECT *\n  FROM users\nWHERE id = 1;\tend".to_string()));
+        assert_eq!(lox.get_value::<String>("single"), Ok("one \"quoted\" line".to_string()));
+        assert_eq!(lox.get_value::<f64>("count"), Ok(2.0));
+
+        let err = App::new().try_run("var q = \"\"\"\n  a\n  \"\"\";\nvar after = 1 - \"x\";").expect_err("Expected runtime error");
+        assert_eq!(err.token().line, 4);
+
+        let diagnostics = App::new().check("script", "var a = 1;\nvar b = r#\"never closed\";");
+        assert_eq!(diagnostics.errors[0].message(), "Unterminated raw string.");
+        assert_eq!(diagnostics.errors[0].token().line, 2);
+    }
+
+    #[test]
+    fn token_dump_and_highlight() {
+        let source = "num n = 0xFF; // max\nprint \"a<b\";";
+        let scanner = App::new().tokenize("script", source);
+
+        let json = scanner.tokens_json();
+        assert!(json.starts_with("{\"tokens\":[{\"kind\":\"Number\",\"class\":\"type\",\"lexeme\":\"num\",\"line\":1,\"column\":1,\"start\":0,\"end\":3}"));
+        assert!(json.contains("{\"kind\":\"Number\",\"class\":\"number\",\"lexeme\":\"0xFF\",\"line\":1,\"column\":9,\"start\":8,\"end\":12,\"value\":255}"));
+        assert!(json.contains("{\"kind\":\"Comment\",\"class\":\"comment\",\"lexeme\":\"// max\""));
+        assert!(json.contains("\"lexeme\":\"\\\"a<b\\\"\",\"line\":2,\"column\":7"));
+        assert!(json.ends_with("\"errors\":[]}"));
+
+        let ansi = to_ansi(source, &scanner.tokens);
+        assert_eq!(ansi, "\x1b[36mnum\x1b[0m n \x1b[34m=\x1b[0m \x1b[33m0xFF\x1b[0m; \x1b[90m// max\x1b[0m\n\x1b[35mprint\x1b[0m \x1b[32m\"a<b\"\x1b[0m;");
+
+        let html = to_html("script", source, &scanner.tokens);
+        assert!(html.contains("<span class=\"keyword\">print</span> <span class=\"string\">&quot;a&lt;b&quot;</span>"));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn match_statement() {
+        let mut lox = App::new();
+        lox.run("
+            class Shape {
+                Shape() {
+                    this.sides = 0;
+                }
+            }
+            class Point : Shape {
+                Point(x) {
+                    this.x = x;
+                }
+            }
+            fn describe(v) {
+                match v {
+                    1 | 2 => return \"small\";
+                    num n if n > 10 => return \"big \" + (n as string);
+                    num n => return \"num \" + (n as string);
+                    \"hi\" => return \"greeting\";
+                    string s => return \"string \" + s;
+                    Point p => return \"point \" + (p.x as string);
+                    Shape _ => return \"shape\";
+                    [] => return \"empty\";
+                    [first, ...rest] => return \"head \" + (first as string) + \" of \" + ((#rest + 1) as string);
+                    _ => return \"other\";
+                }
+            }
+            var results = [
+                describe(2), describe(42), describe(5), describe(\"hi\"), describe(\"yo\"),
+                describe(Point(3)), describe(Shape()), describe([]), describe([7, 8, 9]), describe(true)
+            ];
+        ");
+
+        assert_eq!(lox.get_vec::<String>("results"), Ok(vec![
+            "small".to_string(), "big 42".to_string(), "num 5".to_string(), "greeting".to_string(),
+            "string yo".to_string(), "point 3".to_string(), "shape".to_string(), "empty".to_string(),
+            "head 7 of 3".to_string(), "other".to_string()
+        ]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("var a = 1;\nmatch \"x\" {\n    num n => print(n);\n}").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::NonExhaustiveMatch(_)));
+        assert_eq!(err.message(), "Non-exhaustive match, no arm matched x.");
+        assert_eq!((err.span().line, err.span().column), (2, 1));
+        assert!(lox.render_error(&err).contains("2 | match \"x\" {\n  | ^^^^^"));
+    }
+
+    #[test]
+    fn destructuring() {
+        let mut lox = App::new();
+        lox.run("
+            class Person {
+                Person(name, age) {
+                    this.name = name;
+                    this.age = age;
+                }
+            }
+            fn total([a, b], {age}) {
+                return a + b + age;
+            }
+            var [x, y] = [1, 2];
+            [x, y] = [y, x];
+            var [head, ...tail] = [1, 2, 3];
+            var {name, age} = Person(\"Ada\", 36);
+            var labels = [];
+            for [k, v] in [[\"a\", 1], [\"b\", 2]] {
+                labels += k + (v as string);
+            }
+            var results = [x, y, head, #tail, age, total([3, 4], Person(\"Bob\", 5))];
+            var who = nil;
+            {name: who, age} = Person(\"Cy\", 7);
+        ");
+
+        assert_eq!(lox.get_vec::<f64>("results"), Ok(vec![2.0, 1.0, 1.0, 2.0, 36.0, 12.0]));
+        assert_eq!(lox.get_vec::<String>("labels"), Ok(vec!["a1".to_string(), "b2".to_string()]));
+        assert_eq!(lox.get_value::<String>("who"), Ok("Cy".to_string()));
+        assert_eq!(lox.get_value::<f64>("age"), Ok(7.0));
+
+        let mut lox = App::new();
+        let err = lox.try_run("var [a, b] = [1, 2, 3];").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::IndexOutOfRange(_)));
+        assert_eq!(err.message(), "Expected 2 elements but got 3.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("class P {\n    P() { this.name = 1; }\n}\nvar {name, age} = P();").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::UndefinedProperty(_)));
+        assert_eq!(err.message(), "Undefined property 'age' on P instance.");
+        assert_eq!((err.span().line, err.span().column), (4, 12));
+    }
+
+    #[test]
+    fn nil_coalescing_and_optional_chaining() {
+        let mut lox = App::new();
+        lox.run("
+            class Node {
+                Node(value, next) {
+                    this.value = value;
+                    this.next = next;
+                }
+                describe() {
+                    return \"node \" + (this.value as string);
+                }
+            }
+            var calls = 0;
+            fn fallback() {
+                calls += 1;
+                return 0;
+            }
+            var list = Node(1, Node(2, nil));
+            var items = nil;
+            var pair = [7, 8];
+            var x = nil;
+            x ??= 3;
+            x ??= fallback();
+            var numbers = [list.next?.value, list.next.next?.value ?? -1, pair?[1], 1 ?? fallback(), x];
+            var strings = [list.next.next?.describe() ?? \"none\", list?.describe(), items?[0] ?? \"empty\", items?.head.value ?? \"skipped\"];
+            var flag = false ?? true;
+            var picked = flag ?[1] : [2];
+        ");
+
+        assert_eq!(lox.get_value::<f64>("calls"), Ok(0.0));
+        assert_eq!(lox.get_vec::<f64>("numbers"), Ok(vec![2.0, -1.0, 8.0, 1.0, 3.0]));
+        assert_eq!(lox.get_vec::<String>("strings"), Ok(vec!["none".to_string(), "node 1".to_string(), "empty".to_string(), "skipped".to_string()]));
+        assert_eq!(lox.get_value::<bool>("flag"), Ok(false));
+        assert_eq!(lox.get_vec::<f64>("picked"), Ok(vec![2.0]));
+
+        let mut lox = App::new();
+        lox.run("
+            class Slot {
+                Slot() {
+                    this.value = nil;
+                    this.reads = 0;
+                    this.stores = 0;
+                }
+                next() {
+                    this.reads += 1;
+                    return 1;
+                }
+                __index__(i) { return this.value; }
+                __setindex__(i, v) {
+                    this.stores += 1;
+                    this.value = v;
+                }
+            }
+            var slot = Slot();
+            var slots = [nil, 2];
+            slots[slot.next()] ??= 5;
+            slots[slot.next() - 1] ??= 6;
+            slot[0] ??= 3;
+            slot[0] ??= 4;
+            var stored = [slot.reads, slot.value, slot.stores];
+        ");
+
+        assert_eq!(lox.get_vec::<f64>("slots"), Ok(vec![6.0, 2.0]));
+        assert_eq!(lox.get_vec::<f64>("stored"), Ok(vec![2.0, 3.0, 1.0]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("class P {\n    P() { this.f = nil; }\n}\nvar p = P();\np?.f();").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Expected variable expression.");
+        assert_eq!(err.span().line, 5);
+    }
+
+    #[test]
+    fn slices() {
+        let mut lox = App::new();
+        lox.run("
+            var list = [0, 1, 2, 3, 4, 5];
+            var middle = list[1:4];
+            var init = list[:-1];
+            var backwards = list[::-1];
+            var tail = list[-2:];
+            var past_end = list[10:];
+            string s = \"hello world\";
+            var every_other = s[::2];
+            list[1:3] = [9, 9, 9];
+            var spliced = list;
+            list[::3] = [7, 7, 7];
+            s[0:5] = \"HELLO\";
+        ");
+
+        assert_eq!(lox.get_vec::<f64>("middle"), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(lox.get_vec::<f64>("init"), Ok(vec![0.0, 1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(lox.get_vec::<f64>("backwards"), Ok(vec![5.0, 4.0, 3.0, 2.0, 1.0, 0.0]));
+        assert_eq!(lox.get_vec::<f64>("tail"), Ok(vec![4.0, 5.0]));
+        assert_eq!(lox.get_vec::<f64>("past_end"), Ok(vec![]));
+        assert_eq!(lox.get_value::<String>("every_other"), Ok("hlowrd".to_string()));
+        assert_eq!(lox.get_vec::<f64>("spliced"), Ok(vec![0.0, 9.0, 9.0, 9.0, 3.0, 4.0, 5.0]));
+        assert_eq!(lox.get_vec::<f64>("list"), Ok(vec![7.0, 9.0, 9.0, 7.0, 3.0, 4.0, 7.0]));
+        assert_eq!(lox.get_value::<String>("s"), Ok("HELLO world".to_string()));
+
+        let mut lox = App::new();
+        let err = lox.try_run("var l = [1, 2, 3];\nl[::2] = [1];").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::IndexOutOfRange(_)));
+        assert_eq!(err.message(), "Cannot assign 1 elements to a slice of 2 elements.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("var m = [1, 2, 3];\nm[0:1.5];").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+        assert_eq!(err.message(), "Slice bounds must be integers but got 1.5.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("var m = [1, 2, 3];\nm[1.5..3];").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+    }
+
+    #[test]
+    fn maps() {
+        let mut lox = App::new();
+        lox.run("
+            class Point {
+                Point(x, y) {
+                    this.x = x;
+                    this.y = y;
+                }
+                hash() {
+                    return (this.x as string) + \",\" + (this.y as string);
+                }
+            }
+            var m = {\"a\": 1, \"b\": [2, 3]};
+            m[\"a\"] += 5;
+            m[\"b\"][0] = 9;
+            m[\"c\"] = true;
+            m -= \"c\";
+            var keys = [];
+            for k, v in m {
+                keys += k;
+            }
+            var labels = {};
+            labels[Point(1, 2)] = \"p\";
+            var found = [\"a\" in m, \"c\" in m, Point(1, 2) in labels, 3 in [1, 2, 3], \"ell\" in \"hello\"];
+            var numbers = [m[\"a\"], m[\"b\"][0], #m];
+            var shown = m as string;
+            var map = Hashmap();
+            map.insert(\"k\", 4);
+            var from_std = map.get(\"k\");
+        ");
+
+        assert_eq!(lox.get_vec::<String>("keys"), Ok(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(lox.get_vec::<bool>("found"), Ok(vec![true, false, true, true, true]));
+        assert_eq!(lox.get_vec::<f64>("numbers"), Ok(vec![6.0, 9.0, 2.0]));
+        assert_eq!(lox.get_value::<String>("shown"), Ok("{a: 6, b: [9, 3]}".to_string()));
+        assert_eq!(lox.get_value::<f64>("from_std"), Ok(4.0));
+
+        let mut lox = App::new();
+        let err = lox.try_run("var m = {\"a\": 1};\nvar b = m[\"b\"];").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::IndexOutOfRange(_)));
+        assert_eq!(err.message(), "Key 'b' not found in map.");
+    }
+
+    #[test]
+    fn ranges() {
+        let mut lox = App::new();
+        lox.run("
+            var up = [];
+            for i in 0..4 {
+                up += i;
+            }
+            var down = [];
+            for i in 10..=0 step -5 {
+                down += i;
+            }
+            var empty = [];
+            for i in 3..0 {
+                empty += i;
+            }
+            var r = 1..10 step 3;
+            var list = [0, 1, 2, 3, 4, 5];
+            var middle = list[1..=3];
+            var backwards = list[5..0 step -2];
+            var numbers = [#r, r[1], r[-1], #(0..=4)];
+            var found = [4 in r, 5 in r, 10 in r, 2 in 0..=2];
+            var shown = (10..0 step -2) as string;
+        ");
+
+        assert_eq!(lox.get_vec::<f64>("up"), Ok(vec![0.0, 1.0, 2.0, 3.0]));
+        assert_eq!(lox.get_vec::<f64>("down"), Ok(vec![10.0, 5.0, 0.0]));
+        assert_eq!(lox.get_vec::<f64>("empty"), Ok(vec![]));
+        assert_eq!(lox.get_vec::<f64>("middle"), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(lox.get_vec::<f64>("backwards"), Ok(vec![5.0, 3.0, 1.0]));
+        assert_eq!(lox.get_vec::<f64>("numbers"), Ok(vec![3.0, 4.0, 7.0, 5.0]));
+        assert_eq!(lox.get_vec::<bool>("found"), Ok(vec![true, false, false, true]));
+        assert_eq!(lox.get_value::<String>("shown"), Ok("10..0 step -2".to_string()));
+
+        let mut lox = App::new();
+        let err = lox.try_run("var r = 0..5 step 0;").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+        assert_eq!(err.message(), "Range step cannot be zero.");
+    }
+
+    #[test]
+    fn parameters() {
+        let mut lox = App::new();
+        lox.run("
+            fn greet(name, greeting = \"hello\", punct = \"!\") {
+                return greeting + \" \" + name + punct;
+            }
+            fn scale(x, factor = x * 2) {
+                return x * factor;
+            }
+            fn sum(first, ...rest) {
+                for r in rest {
+                    first += r;
+                }
+                return first;
+            }
+            class Point {
+                Point(x = 0, y = 0) {
+                    this.x = x;
+                    this.y = y;
+                }
+            }
+            var nums = [1, 2, 3];
+            var p = Point(y: 5);
+            var greetings = [greet(\"bob\"), greet(\"bob\", \"hi\"), greet(\"bob\", punct: \"?\"), greet(punct: \".\", name: \"al\")];
+            var numbers = [scale(3), sum(1), sum(...nums, 4), sum(0, ...1..=3), p.x, p.y];
+        ");
+
+        assert_eq!(lox.get_vec::<String>("greetings"), Ok(vec![
+            "hello bob!".to_string(), "hi bob!".to_string(), "hello bob?".to_string(), "hello al.".to_string()
+        ]));
+        assert_eq!(lox.get_vec::<f64>("numbers"), Ok(vec![18.0, 1.0, 10.0, 6.0, 0.0, 5.0]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("fn f(a, b = 1) {}\nf(1, 2, 3);").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::Arity(_)));
+        assert_eq!(err.message(), "Expected 1 to 2 arguments but got 3.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("fn f(a, b) {}\nf(1, a: 2);").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Got more than one value for parameter 'a'.");
+    }
+
+    #[test]
+    fn return_types() {
+        let mut lox = App::new();
+        lox.run("
+            fn area(r : num) -> num {
+                return 3 * r * r;
+            }
+            var square => |x| -> num x * x;
+            var shout = |s| -> string { return s + \"!\"; };
+            class Named {
+                name() -> string {
+                    return \"named\";
+                }
+            }
+            var results = [area(2) as string, square(3) as string, shout(\"hi\"), Named().name()];
+        ");
+
+        assert_eq!(lox.get_vec::<String>("results"), Ok(vec!["12".to_string(), "9".to_string(), "hi!".to_string(), "named".to_string()]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("fn f(x) -> num {\n  return x;\n}\nf(\"a\");").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+        assert_eq!(err.message(), "Function 'f' should return num but returned string.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("fn f() -> bool {}\nf();").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Function 'f' should return bool but returned nil.");
+    }
+
+    #[test]
+    fn type_annotations() {
+        let mut lox = App::new();
+        lox.run("
+            class Shape {}
+            class Point : Shape {
+                Point(x, y) {
+                    this.x = x;
+                    this.y = y;
+                }
+            }
+            fn first(items : list<num>) -> num? {
+                if #items > 0 {
+                    return items[0];
+                }
+                return nil;
+            }
+            list<num> xs = [1, 2];
+            xs[0] = 10;
+            list<list<num>> grid = [[1], [2, 3]];
+            map m = {\"a\": 1};
+            fn double => |x| x * 2;
+            Shape s = Point(3, 4);
+            Point? p = nil;
+            p = Point(5, 6);
+            var results = [xs[0], double(2), s.x, p.y, first([7]), first([]) ?? -1];
+        ");
+
+        assert_eq!(lox.get_vec::<f64>("results"), Ok(vec![10.0, 4.0, 3.0, 6.0, 7.0, -1.0]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("list<num> xs = [1];\nxs += \"a\";").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::TypeMismatch(_)));
+        assert_eq!(err.message(), "Cannot assign list to 'xs' of type list<num>.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("class A {}\nclass B {}\nA a = B();").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Cannot assign B instance to 'a' of type A.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("class P {}\nfn f(p : P) {}\nf(nil);").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Invalid arg for parameter 'p'. Expected type 'P'");
+    }
+
+    #[test]
+    fn interfaces() {
+        let mut lox = App::new();
+        lox.run("
+            interface Drawable {
+                draw();
+                area() -> num;
+            }
+            class Shape impl Drawable {
+                abstract area();
+                draw() { return \"area \" + (this.area() as string); }
+            }
+            class Square : Shape {
+                Square(side) { this.side = side; }
+                area() -> num { return this.side * this.side; }
+            }
+            class Plain {}
+            fn paint(d : Drawable) { return d.draw(); }
+            var drawn = paint(Square(3));
+            var checks = [Square(1) is Drawable, Square(1) is Shape, Plain() is Drawable];
+        ");
+
+        assert_eq!(lox.get_value::<String>("drawn"), Ok("area 9".to_string()));
+        assert_eq!(lox.get_vec::<bool>("checks"), Ok(vec![true, true, false]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("interface Drawable { draw(); }\nclass Circle impl Drawable {}").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Class 'Circle' is missing method 'draw' from interface 'Drawable'.");
+
+        let mut lox = App::new();
+        let err = lox.try_run("class Shape { abstract area(); }\nvar s = Shape();").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::NotCallable(_)));
+        assert_eq!(err.message(), "Cannot create an instance of abstract class 'Shape', 'area' has no body.");
+    }
+
+    #[test]
+    fn statics_and_properties() {
+        let mut lox = App::new();
+        lox.run("
+            class Rect {
+                static made = 0;
+                Rect(w, h) { this.w = w; this.h = h; this.made += 1; }
+                static square(side) { return Rect(side, side); }
+                get area -> num { return this.w * this.h; }
+                set area(v) { this.w = v / this.h; }
+            }
+            class Box : Rect {
+                Box(w, h) { this.w = w; this.h = h; }
+            }
+            var r = Rect.square(2);
+            var before = r.area;
+            r.area = 8;
+            var width = r.w;
+            r.area += 4;
+            var b = Box(3, 1);
+            Box.made += 10;
+            var counts = [Rect.made, b.made, b.area];
+        ");
+
+        assert_eq!(lox.get_value::<f64>("before"), Ok(4.0));
+        assert_eq!(lox.get_value::<f64>("width"), Ok(4.0));
+        assert_eq!(lox.get_vec::<f64>("counts"), Ok(vec![11.0, 11.0, 3.0]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("class Rect {}\nRect.made = 1;").expect_err("Expected runtime error");
+        assert_eq!(err.message(), "Class 'Rect' has no static field 'made'.");
+    }
+
+    #[test]
+    fn operator_overloading() {
+        let mut lox = App::new();
+        lox.run("
+            class Vec {
+                Vec(x, y) { this.x = x; this.y = y; }
+                __add__(o) { return Vec(this.x + o.x, this.y + o.y); }
+                __eq__(o) { return this.x == o.x; }
+                __index__(i) { var items = [this.x, this.y]; return items[i]; }
+                __setindex__(i, v) { this.x = v; }
+                __len__() { return 2; }
+                __str__() { return \"Vec(${this.x}, ${this.y})\"; }
+                __call__(k) { return this.y * k; }
+            }
+            var a = Vec(1, 2) + Vec(2, 4);
+            a[0] = 5;
+            var shown = [a, Vec(0, 0)] as string;
+            var checks = [a == Vec(5, 1), a != a];
+            var numbers = [a[1], #a, a(2)];
+
+            var h = Hashmap();
+            h[\"x\"] = 1;
+            h[\"x\"] += 2;
+            var stored = [h[\"x\"], #h];
+        ");
+
+        assert_eq!(lox.get_value::<String>("shown"), Ok("[Vec(5, 6), Vec(0, 0)]".to_string()));
+        assert_eq!(lox.get_vec::<bool>("checks"), Ok(vec![true, false]));
+        assert_eq!(lox.get_vec::<f64>("numbers"), Ok(vec![6.0, 2.0, 12.0]));
+        assert_eq!(lox.get_vec::<f64>("stored"), Ok(vec![3.0, 1.0]));
+
+        let mut lox = App::new();
+        let err = lox.try_run("
+            class Bad { __eq__(o) { return nil + 1; } }
+            match Bad() { 1 => println(\"one\"); _ => println(\"wild\"); }
+        ").expect_err("Expected __eq__ to fail the match");
+        assert_eq!(err.message(), "Operands must be Numbers Or Strings.");
+        assert!(lox.interpreter.environment.get(&lox.interpreter, Token::new(TokenType::Identifier, "o", None, 0)).is_err());
+        assert_eq!(err.stack()[0].line, 3);
+
+        let mut lox = App::new();
+        let err = lox.try_run("class S { __str__() { return 1; } }\nprintln(S());").expect_err("Expected __str__ to fail");
+        assert_eq!(err.message(), "__str__ of S must return a string but returned num.");
+        assert_eq!((err.span().line, err.span().column), (2, 1));
+    }
+
+    #[test]
+    fn exceptions() {
+        let mut lox = App::new();
+        lox.run("
+            var caught = [];
+            fn check(n) {
+                if n > 2 {
+                    throw Error(\"too big\");
+                }
+                return n;
+            }
+            try {
+                check(5);
+            } catch (e) {
+                caught += e as string;
+                caught += e.line;
+            } finally {
+                caught += \"finally\";
+            }
+            try {
+                var x = -\"a\";
+            } catch (e) {
+                caught += e.kind;
+                caught += e.message;
+            }
+            try {
+                throw \"plain\";
+            } catch (e) {
+                caught += e;
+            }
+            fn leave() {
+                try {
+                    return 1;
+                } finally {
+                    return 2;
+                }
+            }
+            var left = leave();
+        ");
+
+        assert_eq!(lox.get_value::<f64>("left"), Ok(2.0));
+        let caught = lox.get_value_raw("caught").map(|c| lox.interpreter.print_helper(Some(c), false, 0));
+        assert_eq!(caught, Some("[Error: too big, 5, finally, TypeMismatch, Operand must be a Number., plain]".to_string()));
+
+        let mut lox = App::new();
+        let err = lox.try_run("throw Error(\"boom\");").expect_err("Expected uncaught error");
+        assert!(matches!(err, LoxError::Thrown(_, _)));
+        assert_eq!(err.message(), "Uncaught Error: boom.");
+
+        let mut lox = App::new();
+        lox.run("
+            class Oops {
+                Oops(code) { this.code = code; }
+            }
+            class NotFound : Error {
+                NotFound(what) { super.Error(what + \" not found\", \"NotFound\"); }
+            }
+            var caught = [];
+            try {
+                throw Oops(7);
+            } catch (e) {
+                caught += e.code;
+            }
+            try {
+                throw NotFound(\"key\");
+            } catch (e) {
+                caught += e.kind;
+                caught += e.line;
+                caught += e as string;
+            }
+        ");
+
+        let caught = lox.get_value_raw("caught").map(|c| lox.interpreter.print_helper(Some(c), false, 0));
+        assert_eq!(caught, Some("[7, NotFound, 15, NotFound: key not found]".to_string()));
+    }
+
+    #[test]
+    fn modules() {
+        let dir = std::env::temp_dir().join(format!("rlox_modules_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("util")).unwrap();
+        std::fs::write(dir.join("shapes.lox"), "var loads = 1;\nfn area(r) { return sq(r) * 3; }\nfn sq(x) { return x * x; }\n").unwrap();
+        std::fs::write(dir.join("util").join("text.lox"), "import \"../shapes.lox\" as sh;\nfn shout(s) { return s + \"!\"; }\n").unwrap();
+        std::fs::write(dir.join("geometry.lox"), "var PI = 3;\nfn area(r) { return PI * r * r; }\n").unwrap();
+        std::fs::write(dir.join("a.lox"), "import \"b.lox\";\n").unwrap();
+        std::fs::write(dir.join("b.lox"), "import \"a.lox\";\n").unwrap();
+
+        let mut lox = App::new();
+        lox.add_search_path(dir.to_str().unwrap());
+        lox.run("
+            import \"shapes.lox\";
+            import \"util/text.lox\" as t;
+            from \"shapes.lox\" import sq;
+            fn area(x) { return -1; }
+            import \"geometry.lox\";
+            var results = [shapes.area(2), sq(3), area(0), t.shout(\"hi\"), t.sh.loads, geometry.area(2), PI];
+        ");
+
+        assert_eq!(lox.get_value_raw("results").map(|r| lox.interpreter.print_helper(Some(r), false, 0)), Some("[12, 9, -1, hi!, 1, 12, 3.14159265359]".to_string()));
+        assert_eq!(lox.interpreter.modules.len(), 3);
+
+        let mut lox = App::new();
+        lox.add_search_path(dir.to_str().unwrap());
+        let err = lox.try_run("import \"a.lox\";").expect_err("Expected circular import");
+        assert!(matches!(err, LoxError::Import(_)));
+        assert_eq!(err.message(), "Circular import: a.lox -> b.lox -> a.lox.");
+
+        let err = App::new().try_run("from \"missing.lox\" import x;").expect_err("Expected missing module");
+        assert_eq!(err.message(), "Cannot find module 'missing.lox'.");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn enums() {
+        let mut lox = App::new();
+        lox.run("
+            enum Color { Red, Green, Blue }
+            enum Shape { Circle(r), Rect(w, h), Empty }
+            fn describe(s : Shape) -> string {
+                match s {
+                    Shape.Circle(r) => return \"circle \" + (r as string);
+                    Shape.Rect(w, h) if w == h => return \"square\";
+                    Shape.Rect => return \"rect\";
+                    Shape.Empty => return \"empty\";
+                }
+            }
+            var rect = Shape.Rect(2, 3);
+            var checks = [
+                Color.Red == Color.Red, Color.Red == Color.Blue, rect == Shape.Rect(2, 3), rect == Shape.Rect(3, 2),
+                Color.Green is Color, Color.Green is Shape, rect is Shape.Rect, rect is Shape.Circle,
+                Color.Red != Color.Blue, Color.Red != Color.Red, rect != Shape.Rect(3, 2)
+            ];
+            var names = [];
+            for c in Color.values() {
+                names += c as string;
+            }
+            var shapes = [describe(Shape.Circle(1)), describe(Shape.Rect(2, 2)), describe(rect), describe(Shape.Empty), rect as string];
+            var area = rect.w * rect.h;
+        ");
+
+        assert_eq!(lox.get_vec::<bool>("checks"), Ok(vec![true, false, true, false, true, false, true, false, true, false, true]));
+        assert_eq!(lox.get_vec::<String>("names"), Ok(vec!["Color.Red".to_string(), "Color.Green".to_string(), "Color.Blue".to_string()]));
+        assert_eq!(lox.get_vec::<String>("shapes"), Ok(vec![
+            "circle 1".to_string(), "square".to_string(), "rect".to_string(), "empty".to_string(), "Shape.Rect(2, 3)".to_string()
+        ]));
+        assert_eq!(lox.get_value::<f64>("area"), Ok(6.0));
+
+        let mut lox = App::new();
+        let err = lox.try_run("enum Color { Red }\nvar c = Color.Purple;").expect_err("Expected runtime error");
+        assert!(matches!(err, LoxError::UndefinedProperty(_)));
+        assert_eq!(err.message(), "Enum 'Color' has no variant 'Purple'.");
+    }
 }
@@ -1,24 +1,60 @@
+use std::collections::HashMap;
+
 use crate::environment::Environment;
 use crate::types::*;
+use crate::errors::*;
 use crate::tokens::*;
 use crate::expressions::*;
 use crate::statements::*;
-use crate::application::*;
+use crate::diagnostics::*;
 
 pub struct Parser {
     pub tokens : Vec<Token>,
     pub current : i32,
     pub in_class : bool,
-    pub in_derived : bool
+    pub in_derived : bool,
+    pub in_static : bool,
+    pub in_ternary : bool,
+    pub diagnostics : Diagnostics,
+    pub docs : HashMap<i32, String>
 }
 
 impl Parser {
+    //doc comments are pulled out of the stream and keyed by the token they document
     pub fn new(tokens : Vec<Token>) -> Self {
+        let mut docs : HashMap<i32, String> = HashMap::new();
+        let mut kept = Vec::new();
+
+        for token in tokens {
+            if token.type_ == TokenType::Comment {
+                continue;
+            }
+            if token.type_ != TokenType::DocComment {
+                kept.push(token);
+                continue;
+            }
+
+            let text = match token.literal {
+                Some(Literal::String(s)) => s,
+                _ => String::new()
+            };
+            docs.entry(kept.len() as i32)
+                .and_modify(|d| {
+                    d.push('\n');
+                    d.push_str(&text);
+                })
+                .or_insert(text);
+        }
+
         Self {
-            tokens,
+            tokens : kept,
             current : 0,
             in_class : false,
-            in_derived : false
+            in_derived : false,
+            in_ternary : false,
+            in_static : false,
+            diagnostics : Diagnostics::new(),
+            docs
         }
     }
     
@@ -27,7 +63,7 @@ impl Parser {
     }
     
     fn assignment(&mut self) -> RuntimeError<Box<dyn Expr>> {
-        let expr = self.or()?;
+        let expr = self.coalesce()?;
         
         if self.try_match(vec!(TokenType::Equal)) {
             let equals = self.previous();
@@ -43,8 +79,11 @@ impl Parser {
             else if let Some(v) = expr.as_any().downcast_ref::<IndexGet>() {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), None)));
             }
+            else if let (true, Some(pattern)) = (expr.as_any().is::<Index>(), self.assign_pattern(expr.as_ref())) {
+                return Ok(Box::new(AssignPattern::new(equals, pattern, value)));
+            }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -63,7 +102,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), Some(TokenType::Plus))));
             }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -82,7 +121,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), Some(TokenType::Minus))));
             }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -101,7 +140,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), Some(TokenType::Star))));
             }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -120,7 +159,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), Some(TokenType::Slash))));
             }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -139,7 +178,27 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value.clone(), Some(TokenType::Mod))));
             }
             else {
-                App::error(equals, "Invalid assignment target.");
+                self.error(equals, "Invalid assignment target.");
+                return Ok(expr) 
+            }
+        }
+        //x ??= y only evaluates y, and only stores it, when x is nil
+        else if self.try_match(vec!(TokenType::QuestionQuestionEqual)) {
+            let equals = self.previous();
+            let value = self.assignment()?;
+            let assign_type = Some(TokenType::QuestionQuestion);
+
+            if let Some(v) = expr.as_any().downcast_ref::<VarExpr>() {
+                return Ok(Box::new(Assign::new(v.name.clone(), value, assign_type)))
+            }
+            else if let Some(v) = expr.as_any().downcast_ref::<Get>() {
+                return Ok(Box::new(Set::new(v.name.clone(), v.object.clone(), value, assign_type)));
+            }
+            else if let Some(v) = expr.as_any().downcast_ref::<IndexGet>() {
+                return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), value, assign_type)));
+            }
+            else {
+                self.error(equals, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -157,7 +216,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), Box::new(LiteralExp::new(Some(Literal::Number(1.0)))), Some(TokenType::Plus))));
             }
             else {
-                App::error(incr, "Invalid assignment target.");
+                self.error(incr, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -175,7 +234,7 @@ impl Parser {
                 return Ok(Box::new(IndexSet::new(v.keyword.clone(), v.index.clone(), v.object.clone(), Box::new(LiteralExp::new(Some(Literal::Number(1.0)))), Some(TokenType::Minus))));
             }
             else {
-                App::error(decr, "Invalid assignment target.");
+                self.error(decr, "Invalid assignment target.");
                 return Ok(expr) 
             }
         }
@@ -184,26 +243,26 @@ impl Parser {
         }
     }
     
+    //skip ahead to the next statement boundary after a syntax error
     fn synchronize(&mut self) {
         self.advance();
         
         while !self.is_at_end() {
             if self.previous().type_ == TokenType::Semicolon {
-                break;
+                return;
             }
             
+            //num, bool and string share their token type with literals, only the type names start a declaration
             match self.peek().type_ {
-                TokenType::Class => {},
-                TokenType::Fn => {},
-                TokenType::Var => {},
-                TokenType::For => {},
-                TokenType::If => {},
-                TokenType::While => {},
-                TokenType::Print => {},
-                TokenType::PrintLn => {},
-                TokenType::Break => {},
-                TokenType::Return => break,
-                _ => break
+                TokenType::Number | TokenType::Bool | TokenType::String if self.peek().literal.is_some() => { },
+                TokenType::Class | TokenType::Fn | TokenType::Var |
+                TokenType::Number | TokenType::Bool | TokenType::String |
+                TokenType::For | TokenType::If | TokenType::While |
+                TokenType::Print | TokenType::PrintLn | TokenType::Return |
+                TokenType::Break | TokenType::Continue | TokenType::Match |
+                TokenType::Enum | TokenType::Interface | TokenType::Throw | TokenType::Try |
+                TokenType::Import | TokenType::RightBrace => return,
+                _ => { }
             }
             
             self.advance();
@@ -222,12 +281,27 @@ impl Parser {
         Ok(expr)
     }
     
+    fn coalesce(&mut self) -> RuntimeError<Box<dyn Expr>> {
+        let mut expr = self.or()?;
+        
+        while self.try_match(vec!(TokenType::QuestionQuestion)) {
+            let operator = self.previous();
+            let right = self.or()?;
+            expr = Box::new(Logical::new(expr, operator, right));
+        }
+        
+        Ok(expr)
+    }
+    
     fn or(&mut self) -> RuntimeError<Box<dyn Expr>> {
         let mut expr = self.and()?;
         
         if self.try_match(vec!(TokenType::Question)) {
             let operator_a = self.previous();
-            let left = self.and()?;
+            let enclosing = std::mem::replace(&mut self.in_ternary, true);
+            let left = self.and();
+            self.in_ternary = enclosing;
+            let left = left?;
             let operator_b = self.consume(TokenType::Colon, "Expect ':'.")?;
             let right = self.and()?;
             return Ok(Box::new(Ternary::new(expr, operator_a, left, operator_b, right)));
@@ -255,9 +329,9 @@ impl Parser {
     }
     
     fn comparison(&mut self) -> RuntimeError<Box<dyn Expr>> {
-        let mut expr = self.term()?;
+        let mut expr = self.range()?;
 
-        while self.try_match(vec!(TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual, TokenType::Is)) {
+        while self.try_match(vec!(TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual, TokenType::Is, TokenType::In)) {
             let operator = self.previous();
             if operator.type_ == TokenType::Is {
                 if self.try_match(vec!(TokenType::Number)) {
@@ -273,11 +347,13 @@ impl Parser {
                     expr = Box::new(Binary::new(expr, operator, right));
                 }
                 else {
-                    return Err((self.previous(), "Expect type after 'as'".to_string()));
+                    //a class, an enum or one of its variants
+                    let right = self.call()?;
+                    expr = Box::new(Binary::new(expr, operator, right));
                 }
             }
             else { 
-                let right = self.term()?;
+                let right = self.range()?;
                 expr = Box::new(Binary::new(expr, operator, right));
             }
         }
@@ -285,6 +361,27 @@ impl Parser {
         Ok(expr)
     }
     
+    //start..end or start..=end with an optional step, step is only a keyword in this position
+    fn range(&mut self) -> RuntimeError<Box<dyn Expr>> {
+        let expr = self.term()?;
+        
+        if self.try_match(vec!(TokenType::DotDot, TokenType::DotDotEqual)) {
+            let operator = self.previous();
+            let end = self.term()?;
+            
+            let step = match self.check(TokenType::Identifier) && self.peek().lexeme == "step" {
+                true => {
+                    self.advance();
+                    Some(self.term()?)
+                },
+                false => None
+            };
+            return Ok(Box::new(RangeExpr::new(operator, expr, end, step)));
+        }
+        
+        Ok(expr)
+    }
+    
     fn term(&mut self) -> RuntimeError<Box<dyn Expr>> {
         let mut expr = self.factor()?;
         
@@ -317,7 +414,7 @@ impl Parser {
                     expr = Box::new(Binary::new(expr, operator, right));
                 }
                 else {
-                    return Err((self.previous(), "Expect type after 'as'".to_string()));
+                    return Err(LoxError::syntax(self.previous(), "Expect type after 'as'"));
                 }
             }
             else {
@@ -368,6 +465,7 @@ impl Parser {
     
     fn call(&mut self) -> RuntimeError<Box<dyn Expr>> {
         let mut expr = self.primary()?;
+        let mut optional = false;
         
         loop {
             if self.try_match(vec!(TokenType::LeftParen)) {
@@ -378,27 +476,87 @@ impl Parser {
                 expr = Box::new(Get::new(name, expr));
             }
             else if self.try_match(vec!(TokenType::LeftBracket)) {
-                let index = self.expression()?;
+                let index = self.index_or_slice()?;
                 let keyword = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
                 expr = Box::new(IndexGet::new(keyword, index, expr));
             }
+            else if self.try_match(vec!(TokenType::QuestionDot)) {
+                let name = self.consume(TokenType::Identifier, "Expect property name after '?.'.")?;
+                let mut get = Get::new(name, expr);
+                get.optional = true;
+                optional = true;
+                expr = Box::new(get);
+            }
+            else if self.check(TokenType::Question) && self.check_next(TokenType::LeftBracket) && !self.ternary_ahead() {
+                self.advance();
+                self.advance();
+                let index = self.index_or_slice()?;
+                let keyword = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                let mut index_get = IndexGet::new(keyword, index, expr);
+                index_get.optional = true;
+                optional = true;
+                expr = Box::new(index_get);
+            }
             else {
                 break;  
             }
         }
         
+        if optional {
+            expr = Box::new(OptionalChain::new(expr));
+        }
         Ok(expr)
     }
     
+    fn index_or_slice(&mut self) -> RuntimeError<Box<dyn Expr>> {
+        let start = match self.check(TokenType::Colon) {
+            true => None,
+            false => Some(self.expression()?)
+        };
+        
+        if !self.try_match(vec!(TokenType::Colon)) {
+            return Ok(start.unwrap());
+        }
+        let colon = self.previous();
+        
+        let stop = match self.check(TokenType::Colon) || self.check(TokenType::RightBracket) {
+            true => None,
+            false => Some(self.expression()?)
+        };
+        
+        let step = match self.try_match(vec!(TokenType::Colon)) && !self.check(TokenType::RightBracket) {
+            true => Some(self.expression()?),
+            false => None
+        };
+        
+        Ok(Box::new(Slice::new(colon, start, stop, step)))
+    }
+    
     fn finish_call(&mut self, callee : Box<dyn Expr>) -> RuntimeError<Box<dyn Expr>> {
-        let mut arguments = Vec::new();
+        let mut arguments : Vec<Box<dyn Expr>> = Vec::new();
+        let mut named : Vec<(Token, Box<dyn Expr>)> = Vec::new();
         
         if !self.check(TokenType::RightParen) {
             loop {
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     self.error(self.peek(), "Can't have mOre than 255 arguments.");
                 }
-                arguments.push(self.expression()?);
+                
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                    let name = self.advance();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                }
+                else if !named.is_empty() {
+                    return Err(LoxError::syntax(self.peek(), "Positional arguments cannot follow named arguments."));
+                }
+                else if self.try_match(vec!(TokenType::DotDotDot)) {
+                    let dots = self.previous();
+                    arguments.push(Box::new(Spread::new(dots, self.expression()?)));
+                }
+                else {
+                    arguments.push(self.expression()?);
+                }
                 
                 if !self.try_match(vec!(TokenType::Comma)) {
                     break;
@@ -408,7 +566,44 @@ impl Parser {
         
         let paren = self.consume(TokenType::RightParen, "Expect ')' after args.")?;
 
-        Ok(Box::new(Call::new(callee, paren, arguments)))
+        let mut call = Call::new(callee, paren, arguments);
+        call.named = named;
+        Ok(Box::new(call))
+    }
+    
+    //"a ${x} b" arrives as Interpolation("a ") x String(" b") and desugars to "a " + (x as string) + " b"
+    fn interpolation(&mut self) -> RuntimeError<Box<dyn Expr>> {
+        let mut expr : Box<dyn Expr> = Box::new(LiteralExp::new(self.previous().literal));
+
+        loop {
+            let part_start = self.previous();
+            let value = self.expression()?;
+
+            let as_token = Token::new_with_span(TokenType::As, "as", None, part_start.span);
+            let string_type = Box::new(LiteralExp::new(Some(Literal::Keyword("string".to_string()))));
+            let value = Box::new(Binary::new(value, as_token, string_type));
+
+            let plus = Token::new_with_span(TokenType::Plus, "+", None, part_start.span);
+            expr = Box::new(Binary::new(expr, plus.clone(), value));
+
+            let done = if self.try_match(vec!(TokenType::Interpolation)) {
+                false
+            }
+            else if self.check(TokenType::String) && self.peek().literal.is_some() {
+                self.advance();
+                true
+            }
+            else {
+                return Err(LoxError::syntax(self.peek(), "Expect '}' after interpolated expression."));
+            };
+
+            let text = Box::new(LiteralExp::new(self.previous().literal));
+            expr = Box::new(Binary::new(expr, plus, text));
+
+            if done {
+                return Ok(expr);
+            }
+        }
     }
     
     fn primary(&mut self) -> RuntimeError<Box<dyn Expr>> {
@@ -424,6 +619,9 @@ impl Parser {
         else if self.try_match(vec!(TokenType::Number, TokenType::String, TokenType::Bool)) {
             Ok(Box::new(LiteralExp::new(self.previous().literal)))
         }
+        else if self.try_match(vec!(TokenType::Interpolation)) {
+            self.interpolation()
+        }
         else if self.try_match(vec!(TokenType::LeftParen)) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
@@ -432,36 +630,59 @@ impl Parser {
         else if self.try_match(vec!(TokenType::Super)) {
             let keyword = self.previous();
             
-            match self.in_derived {
+            match self.in_derived && !self.in_static {
                 true => {
                     self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
                     let method = self.consume(TokenType::Identifier, "Expect Super-class method name.")?;
             
                     Ok(Box::new(Super::new(keyword, method)))
                 },
-                false => Err((self.previous(), "Found 'super' outside of derived class.".to_string()))
+                false if self.in_static => Err(LoxError::syntax(self.previous(), "Found 'super' in static method.")),
+                false => Err(LoxError::syntax(self.previous(), "Found 'super' outside of derived class."))
             }
         }
         else if self.try_match(vec!(TokenType::This)) {
-            match self.in_class {
+            match self.in_class && !self.in_static {
                 true => Ok(Box::new(This::new(self.previous()))),
-                false => Err((self.previous(), "Found 'this' outside of class.".to_string()))
+                false if self.in_static => Err(LoxError::syntax(self.previous(), "Found 'this' in static method.")),
+                false => Err(LoxError::syntax(self.previous(), "Found 'this' outside of class."))
             }
         }
         else if self.try_match(vec!(TokenType::Identifier)) {
             Ok(Box::new(VarExpr::new(self.previous())))
         }
         
+        else if self.try_match(vec!(TokenType::LeftBrace)) {
+            self.map_literal()
+        }
         else if self.try_match(vec!(TokenType::Pipe)) {
             match self.anon() {
                 Ok(v) => Ok(v),
-                Err((e, v)) => return Err((e, v))
+                Err(e) => return Err(e)
             }
         }
         else {
-            self.error(self.peek(), "Expect expression.");
-            Ok(Box::new(LiteralExp::new(None)))
+            Err(LoxError::syntax(self.peek(), "Expect expression."))
+        }
+    }
+    
+    fn map_literal(&mut self) -> RuntimeError<Box<dyn Expr>> {
+        let brace = self.previous();
+        let mut entries = Vec::new();
+        
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let key = self.expression()?;
+            self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            
+            if !self.try_match(vec!(TokenType::Comma)) {
+                break;
+            }
         }
+        
+        self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+        Ok(Box::new(MapLiteral::new(brace, entries)))
     }
     
     pub fn parse(&mut self) -> RuntimeError<Vec<Box<dyn Stmt>>> {
@@ -473,40 +694,107 @@ impl Parser {
             }
         }
         
-        Ok(statements)
+        self.diagnostics.sort();
+        match self.diagnostics.first() {
+            Some(e) => Err(e),
+            None => Ok(statements)
+        }
     }
     
     fn declaration(&mut self) -> RuntimeError<Option<Box<dyn Stmt>>> {
-        if self.try_match(vec!(TokenType::Var, TokenType::Number, TokenType::Bool, TokenType::String)) {
-            if let Ok(v) = self.var_declaration(self.previous()) {
-                Ok(Some(v))
-            }
-            else {
+        match self.try_declaration() {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                self.diagnostics.push(e);
                 self.synchronize();
                 Ok(None)
             }
         }
+    }
+    
+    fn try_declaration(&mut self) -> RuntimeError<Option<Box<dyn Stmt>>> {
+        let doc = self.docs.remove(&self.current);
+
+        if self.try_match(vec!(TokenType::Var)) {
+            match self.var_declaration(self.previous(), None, doc) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => Err(e)
+            }
+        }
+        else if self.typed_declaration_ahead() {
+            match self.typed_declaration(doc) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => Err(e)
+            }
+        }
         else if self.try_match(vec!(TokenType::Class)) {
-            match self.class_declaration() {
+            match self.class_declaration(doc) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => Err(e)
+            }
+        }
+        else if self.try_match(vec!(TokenType::Enum)) {
+            match self.enum_declaration(doc) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => Err(e)
+            }
+        }
+        else if self.try_match(vec!(TokenType::Interface)) {
+            match self.interface_declaration(doc) {
                 Ok(v) => Ok(Some(v)),
-                Err((e, v)) => Err((e, v))
+                Err(e) => Err(e)
             }
         }
         else if self.try_match(vec!(TokenType::Fn)) {
-            match self.function("function") {
+            match self.function("function", doc) {
                 Ok(v) => Ok(Some(v)),
-                Err((e, v)) => Err((e, v))
+                Err(e) => Err(e)
             }
         }
         else {
             match self.statement() {
                 Ok(v) => Ok(Some(v)),
-                Err((e, v)) => Err((e, v))
+                Err(e) => Err(e)
             }
         }
     }
     
-    fn class_declaration(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+    //enum Shape { Circle(r), Rect(w, h), Empty }
+    fn enum_declaration(&mut self, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+        
+        let mut variants : Vec<(Token, Vec<Token>)> = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let variant = self.consume(TokenType::Identifier, "Expect variant name.")?;
+            if variants.iter().any(|(v, _)| v.lexeme == variant.lexeme) {
+                return Err(LoxError::syntax(variant.clone(), &format!("Duplicate variant '{}' in enum '{}'.", variant.lexeme, name.lexeme)));
+            }
+            
+            let mut fields = Vec::new();
+            if self.try_match(vec!(TokenType::LeftParen)) {
+                loop {
+                    fields.push(self.consume(TokenType::Identifier, "Expect value name.")?);
+                    if !self.try_match(vec!(TokenType::Comma)) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after variant values.")?;
+            }
+            variants.push((variant, fields));
+            
+            if !self.try_match(vec!(TokenType::Comma)) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after enum variants.")?;
+        
+        let mut enumeration = Enum::new(name, variants);
+        enumeration.doc = doc;
+        Ok(Box::new(enumeration))
+    }
+
+    fn class_declaration(&mut self, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
         self.in_class = true;
         
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
@@ -518,21 +806,123 @@ impl Parser {
             self.in_derived = true;
         }
         
+        let mut interfaces = Vec::new();
+        if self.try_match(vec!(TokenType::Impl)) {
+            loop {
+                self.consume(TokenType::Identifier, "Expect interface name.")?;
+                interfaces.push(Box::new(VarExpr::new(self.previous())) as Box<dyn Expr>);
+                if !self.try_match(vec!(TokenType::Comma)) {
+                    break;
+                }
+            }
+        }
+        
         self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
         
         let mut methods = Vec::new();
+        let mut abstracts = Vec::new();
+        let mut statics = Vec::new();
+        let mut fields = Vec::new();
+        let mut getters = Vec::new();
+        let mut setters = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            let method_doc = self.docs.remove(&self.current);
+            if self.try_match(vec!(TokenType::Abstract)) {
+                abstracts.push(self.signature("method", method_doc)?);
+                self.consume(TokenType::Semicolon, "Expect ';' after abstract method.")?;
+            }
+            else if self.try_match(vec!(TokenType::Static)) {
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::LeftParen) {
+                    self.in_static = true;
+                    let method = self.function("static method", method_doc);
+                    self.in_static = false;
+                    statics.push(method?);
+                }
+                else {
+                    let field = self.consume(TokenType::Identifier, "Expect static field name.")?;
+                    let value = match self.try_match(vec!(TokenType::Equal)) {
+                        true => Some(self.expression()?),
+                        false => None
+                    };
+                    self.consume(TokenType::Semicolon, "Expect ';' after static field.")?;
+                    fields.push((field, value));
+                }
+            }
+            //get and set only mean something here, elsewhere they stay plain names
+            else if self.check(TokenType::Identifier) && self.peek().lexeme == "get" && self.check_next(TokenType::Identifier) {
+                self.advance();
+                let name = self.consume(TokenType::Identifier, "Expect property name.")?;
+                let returns = self.return_type()?;
+                self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+                
+                let mut getter = Function::new(name, Vec::new(), self.block()?);
+                getter.doc = method_doc;
+                getter.returns = returns;
+                getters.push(getter);
+            }
+            else if self.check(TokenType::Identifier) && self.peek().lexeme == "set" && self.check_next(TokenType::Identifier) {
+                self.advance();
+                let mut setter = self.signature("property", method_doc)?;
+                if setter.params.len() != 1 {
+                    return Err(LoxError::syntax(*setter.name.clone(), &format!("Setter '{}' must take exactly one parameter.", setter.name.lexeme)));
+                }
+                self.consume(TokenType::LeftBrace, "Expect '{' before setter body.")?;
+                
+                setter.body = self.block()?;
+                setters.push(setter);
+            }
+            else {
+                methods.push(self.function("method", method_doc)?);
+            }
         }
         
         self.consume(TokenType::RightBrace, "Expect '}' after class body")?;
         self.in_class = false;
         self.in_derived = false;
     
-        Ok(Box::new(Class::new(name, methods, super_class)))
+        let mut class = Class::new(name, methods, super_class);
+        class.doc = doc;
+        class.interfaces = interfaces;
+        class.abstracts = abstracts;
+        class.statics = statics;
+        class.fields = fields;
+        class.getters = getters;
+        class.setters = setters;
+        Ok(Box::new(class))
+    }
+    
+    fn interface_declaration(&mut self, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
+        let name = self.consume(TokenType::Identifier, "Expect interface name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before interface body.")?;
+        
+        let mut methods : Vec<Function> = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let method_doc = self.docs.remove(&self.current);
+            let method = self.signature("method", method_doc)?;
+            if methods.iter().any(|m| m.name.lexeme == method.name.lexeme) {
+                return Err(LoxError::syntax(*method.name.clone(), &format!("Duplicate method '{}' in interface '{}'.", method.name.lexeme, name.lexeme)));
+            }
+            self.consume(TokenType::Semicolon, "Expect ';' after interface method.")?;
+            methods.push(method);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after interface body.")?;
+        
+        let mut interface = Interface::new(name, methods);
+        interface.doc = doc;
+        Ok(Box::new(interface))
+    }
+    
+    fn function(&mut self, kind : &str, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
+        let mut function = self.signature(kind, doc)?;
+        
+        self.consume(TokenType::LeftBrace, format!("Expect '{{' before {} body.", kind).as_str())?;
+    
+        function.body = self.block()?;
+        Ok(Box::new(function))
     }
     
-    fn function(&mut self, kind : &str) -> RuntimeError<Box<dyn Stmt>> {
+    //everything up to the body, interfaces and abstract methods stop here
+    fn signature(&mut self, kind : &str, doc : Option<String>) -> RuntimeError<Function> {
         let name = self.consume(TokenType::Identifier, format!("Expect {} name.", kind).as_str())?;
     
         self.consume(TokenType::LeftParen, format!("Expect {} name.", kind).as_str())?;
@@ -544,27 +934,8 @@ impl Parser {
                     self.error(self.peek(), "Can't have mOre than 255 parameters.");
                 }
                 
-                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
-                
-                let binding = if self.try_match(vec!(TokenType::Colon)) {
-                    if self.try_match(vec!(TokenType::Number)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::Bool)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::String)) {
-                        Some(self.previous())
-                    }
-                    else {
-                        return Err((self.previous(), "Expect type after ':'".to_string()));
-                    }
-                }
-                else {
-                    None  
-                };
-                
-                parameters.push(Parameter::new(name, binding));
+                let parameter = self.parameter(&parameters)?;
+                parameters.push(parameter);
                 
                 if !self.try_match(vec!(TokenType::Comma)) {
                     break;
@@ -572,12 +943,12 @@ impl Parser {
             } 
         }
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        let returns = self.return_type()?;
         
-        self.consume(TokenType::LeftBrace, format!("Expect '{{' before {} body.", kind).as_str())?;
-    
-        let body = self.block()?;
-        
-        Ok(Box::new(Function::new(name, parameters, body)))
+        let mut function = Function::new(name, parameters, Vec::new());
+        function.doc = doc;
+        function.returns = returns;
+        Ok(function)
     }
 
     fn anon(&mut self) -> RuntimeError<Box<dyn Expr>> {
@@ -588,27 +959,8 @@ impl Parser {
                 if parameters.len() >= 255 {
                     self.error(self.peek(), "Can't have more than 255 parameters.");
                 }
-                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
-                
-                let binding = if self.try_match(vec!(TokenType::Colon)) {
-                    if self.try_match(vec!(TokenType::Number)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::Bool)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::String)) {
-                        Some(self.previous())
-                    }
-                    else {
-                        return Err((self.previous(), "Expect type after ':'".to_string()));
-                    }
-                }
-                else {
-                    None  
-                };
-                
-                parameters.push(Parameter::new(name, binding));
+                let parameter = self.parameter(&parameters)?;
+                parameters.push(parameter);
                 
                 if !self.try_match(vec!(TokenType::Comma)) {
                     break;
@@ -616,12 +968,15 @@ impl Parser {
             } 
         }
         self.consume(TokenType::Pipe, "Expect '|' after parameters.")?;
+        let returns = self.return_type()?;
         
         self.consume(TokenType::LeftBrace, "Expect '{{' before function body.")?;
     
         let body = self.block()?;
         
-        Ok(Box::new(LiteralExp::new(Some(Literal::Function(Box::new(LoxFunction::new(Function::new(Token::new(TokenType::Identifier, "anon", None, 0), parameters, body), Environment::new(), FunctionType::Anon, false)))))))
+        let mut function = Function::new(Token::new(TokenType::Identifier, "anon", None, 0), parameters, body);
+        function.returns = returns;
+        Ok(Box::new(LiteralExp::new(Some(Literal::Function(Box::new(LoxFunction::new(function, Environment::new(), FunctionType::Anon, false)))))))
     }
     
     fn anon_lambda(&mut self) -> RuntimeError<Box<dyn Expr>> {
@@ -633,27 +988,8 @@ impl Parser {
                     self.error(self.peek(), "Can't have mOre than 255 parameters.");
                 }
                 
-                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
-                
-                let binding = if self.try_match(vec!(TokenType::Colon)) {
-                    if self.try_match(vec!(TokenType::Number)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::Bool)) {
-                        Some(self.previous())
-                    }
-                    else if self.try_match(vec!(TokenType::String)) {
-                        Some(self.previous())
-                    }
-                    else {
-                        return Err((self.previous(), "Expect type after ':'".to_string()));
-                    }
-                }
-                else {
-                    None  
-                };
-                
-                parameters.push(Parameter::new(name, binding));
+                let parameter = self.parameter(&parameters)?;
+                parameters.push(parameter);
                 
                 if !self.try_match(vec!(TokenType::Comma)) {
                     break;
@@ -661,6 +997,7 @@ impl Parser {
             } 
         }
         self.consume(TokenType::Pipe, "Expect '|' after parameters.")?;
+        let returns = self.return_type()?;
     
         let mut body = vec!();
         
@@ -683,10 +1020,106 @@ impl Parser {
             }
         }
         
-        Ok(Box::new(LiteralExp::new(Some(Literal::Function(Box::new(LoxFunction::new(Function::new(Token::new(TokenType::Identifier, "anon", None, 0), parameters, body), Environment::new(), FunctionType::Anon, false)))))))
+        let mut function = Function::new(Token::new(TokenType::Identifier, "anon", None, 0), parameters, body);
+        function.returns = returns;
+        Ok(Box::new(LiteralExp::new(Some(Literal::Function(Box::new(LoxFunction::new(function, Environment::new(), FunctionType::Anon, false)))))))
     }
     
-    fn var_declaration(&mut self, binding : Token) -> RuntimeError<Box<dyn Stmt>> {
+    //a type then a name then '=', '=>' or ';', told apart from an expression statement before anything is consumed
+    //a '{' starts a field assignment rather than a block when its matching '}' is followed by '='
+    fn fields_assignment_ahead(&self) -> bool {
+        let at = |n : usize| self.tokens.get(self.current as usize + n).map(|t| t.type_);
+        if at(1) != Some(TokenType::Identifier) {
+            return false;
+        }
+        let mut n = 1;
+        let mut depth = 1;
+        loop {
+            match at(n) {
+                Some(TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace) => depth += 1,
+                Some(TokenType::RightParen | TokenType::RightBracket) => depth -= 1,
+                Some(TokenType::RightBrace) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return at(n + 1) == Some(TokenType::Equal);
+                    }
+                },
+                Some(TokenType::Semicolon) | None | Some(TokenType::Eof) => return false,
+                _ => {}
+            }
+            n += 1;
+        }
+    }
+
+    //`c ?[1] : [2]` is a ternary, `xs?[1]` an optional index: look for a ':' left unmatched after the brackets
+    fn ternary_ahead(&self) -> bool {
+        let at = |n : usize| self.tokens.get(self.current as usize + n).map(|t| t.type_);
+        let mut n = 1;
+        let mut depth = 0;
+        //inside `c ? xs?[0] : d` the first ':' belongs to the enclosing ternary
+        let mut questions = self.in_ternary as usize;
+        loop {
+            match at(n) {
+                Some(TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace) => depth += 1,
+                Some(TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace) => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return false;
+                    }
+                },
+                Some(TokenType::Question) if depth == 0 => questions += 1,
+                Some(TokenType::Colon) if depth == 0 => {
+                    if questions == 0 {
+                        return true;
+                    }
+                    questions -= 1;
+                },
+                Some(TokenType::Semicolon | TokenType::Comma) if depth == 0 => return false,
+                None | Some(TokenType::Eof) => return false,
+                _ => {}
+            }
+            n += 1;
+        }
+    }
+
+    fn typed_declaration_ahead(&self) -> bool {
+        let at = |n : usize| self.tokens.get(self.current as usize + n).map(|t| t.type_);
+        let is_type = |t : Option<TokenType>| matches!(t, Some(TokenType::Number | TokenType::Bool | TokenType::String | TokenType::Fn | TokenType::Identifier));
+        
+        if !is_type(at(0)) || (at(0) == Some(TokenType::Number) && self.peek().literal.is_some()) {
+            return false;
+        }
+        let mut n = 1;
+        let mut depth = 0;
+        while at(n) == Some(TokenType::Less) || (depth > 0 && (is_type(at(n)) || at(n) == Some(TokenType::Greater) || at(n) == Some(TokenType::Question))) {
+            match at(n) {
+                Some(TokenType::Less) => depth += 1,
+                Some(TokenType::Greater) => depth -= 1,
+                _ => {}
+            }
+            n += 1;
+        }
+        if at(n) == Some(TokenType::Question) {
+            n += 1;
+        }
+        depth == 0 && at(n) == Some(TokenType::Identifier) && matches!(at(n + 1), Some(TokenType::Equal | TokenType::Semicolon | TokenType::Lambda))
+    }
+
+    fn typed_declaration(&mut self, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
+        let binding = self.peek();
+        let annotation = self.type_annotation("Expect type.")?;
+        self.var_declaration(binding, Some(annotation), doc)
+    }
+
+    fn var_declaration(&mut self, binding : Token, annotation : Option<TypeAnnotation>, doc : Option<String>) -> RuntimeError<Box<dyn Stmt>> {
+        if binding.type_ == TokenType::Var && (self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace)) {
+            let pattern = self.destructure_pattern()?;
+            self.consume(TokenType::Equal, "Expect '=' after destructuring pattern.")?;
+            let value = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expect ';' after Variable declaration.")?;
+            return Ok(Box::new(Destructure::new(binding, pattern, value)));
+        }
+
         let name = self.consume(TokenType::Identifier, "Expect Variable name.")?;
         
         let mut initializer : Option<Box<dyn Expr>> = None;
@@ -700,14 +1133,17 @@ impl Parser {
                 Ok(v) => {
                     initializer = Some(v);
                 },
-                Err((e, v)) => return Err((e, v))
+                Err(e) => return Err(e)
             }
         }
         else {
             self.consume(TokenType::Semicolon, "Expect ';' after Variable declaration.")?;
         }
 
-        Ok(Box::new(Var::new(name, binding, initializer)))
+        let mut var = Var::new(name, binding, initializer);
+        var.doc = doc;
+        var.annotation = annotation;
+        Ok(Box::new(var))
     }
 
     fn statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
@@ -735,14 +1171,315 @@ impl Parser {
         else if self.try_match(vec!(TokenType::For)) {
             self.for_statement()
         }
+        else if self.try_match(vec!(TokenType::Match)) {
+            self.match_statement()
+        }
+        else if self.try_match(vec!(TokenType::Throw)) {
+            self.throw_statement()
+        }
+        else if self.try_match(vec!(TokenType::Try)) {
+            self.try_statement()
+        }
+        else if self.try_match(vec!(TokenType::Import)) {
+            self.import_statement()
+        }
+        else if self.check(TokenType::Identifier) && self.peek().lexeme == "from" && self.check_next(TokenType::String) {
+            self.import_from_statement()
+        }
+        else if self.check(TokenType::LeftBrace) && self.fields_assignment_ahead() {
+            self.advance();
+            self.fields_assignment()
+        }
         else if self.try_match(vec!(TokenType::LeftBrace)) {
             Ok(Box::new(Block::new(self.block()?)))
         }
+        //a stray ';', like the one after `var f => |a| { ... };`, is an empty statement, now that every parse error stops a run it can't be one
+        else if self.try_match(vec!(TokenType::Semicolon)) {
+            Ok(Box::new(StmtExpr::new(Box::new(LiteralExp::new(None)))))
+        }
         else {
             self.expression_statement()
         }
     }
     
+    fn match_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' after match value.")?;
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+
+            let mut guard = None;
+            if self.try_match(vec!(TokenType::If)) {
+                guard = Some(self.expression()?);
+            }
+
+            self.consume(TokenType::Lambda, "Expect '=>' after match pattern.")?;
+            let body = self.statement()?;
+            self.try_match(vec!(TokenType::Comma));
+
+            arms.push(MatchArm::new(pattern, guard, body));
+        }
+        let end = self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        //the keyword spans the whole statement so a failed match points at all of it
+        let mut keyword = keyword;
+        keyword.span.end = end.span.end;
+        Ok(Box::new(Match::new(keyword, value, arms)))
+    }
+
+    fn pattern(&mut self) -> RuntimeError<Pattern> {
+        let first = self.single_pattern()?;
+        if !self.check(TokenType::Pipe) {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec!(first);
+        while self.try_match(vec!(TokenType::Pipe)) {
+            alternatives.push(self.single_pattern()?);
+        }
+        Ok(Pattern::Or(alternatives))
+    }
+
+    fn single_pattern(&mut self) -> RuntimeError<Pattern> {
+        let token = self.peek();
+
+        match token.type_ {
+            TokenType::Number | TokenType::String | TokenType::Bool if token.literal.is_some() => {
+                self.advance();
+                Ok(Pattern::Literal(token.literal))
+            },
+            TokenType::Number | TokenType::String | TokenType::Bool => {
+                self.advance();
+                Ok(Pattern::Type(token, self.pattern_binding()))
+            },
+            TokenType::Minus => {
+                self.advance();
+                match self.consume(TokenType::Number, "Expect number after '-' in pattern.")?.literal {
+                    Some(Literal::Number(n)) => Ok(Pattern::Literal(Some(Literal::Number(-n)))),
+                    _ => Err(LoxError::syntax(self.previous(), "Expect number after '-' in pattern."))
+                }
+            },
+            TokenType::True => {
+                self.advance();
+                Ok(Pattern::Literal(Some(Literal::Boolean(true))))
+            },
+            TokenType::False => {
+                self.advance();
+                Ok(Pattern::Literal(Some(Literal::Boolean(false))))
+            },
+            TokenType::Nil => {
+                self.advance();
+                Ok(Pattern::Literal(None))
+            },
+            TokenType::Identifier if token.lexeme == "_" => {
+                self.advance();
+                Ok(Pattern::Wildcard)
+            },
+            TokenType::Identifier if self.check_next(TokenType::Dot) => {
+                self.advance();
+                self.advance();
+                let variant = self.consume(TokenType::Identifier, "Expect variant name after '.'.")?;
+                
+                let mut values = Vec::new();
+                if self.try_match(vec!(TokenType::LeftParen)) {
+                    loop {
+                        values.push(self.pattern()?);
+                        if !self.try_match(vec!(TokenType::Comma)) {
+                            break;
+                        }
+                    }
+                    self.consume(TokenType::RightParen, "Expect ')' after variant patterns.")?;
+                }
+                Ok(Pattern::Variant(token, variant, values))
+            },
+            //a name followed by another name is a class check, a name on its own binds anything
+            TokenType::Identifier => {
+                self.advance();
+                match self.check(TokenType::Identifier) {
+                    true => Ok(Pattern::Class(token, self.pattern_binding())),
+                    false => Ok(Pattern::Binding(token))
+                }
+            },
+            TokenType::LeftBracket => {
+                self.advance();
+                self.list_pattern()
+            },
+            TokenType::LeftBrace => {
+                self.advance();
+                self.fields_pattern()
+            },
+            _ => Err(LoxError::syntax(token, "Expect pattern."))
+        }
+    }
+
+    fn pattern_binding(&mut self) -> Option<Token> {
+        match self.try_match(vec!(TokenType::Identifier)) {
+            true if self.previous().lexeme != "_" => Some(self.previous()),
+            _ => None
+        }
+    }
+
+    fn list_pattern(&mut self) -> RuntimeError<Pattern> {
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        while !self.check(TokenType::RightBracket) && !self.is_at_end() {
+            if self.try_match(vec!(TokenType::DotDotDot)) {
+                rest = Some(self.pattern_binding());
+
+                if !self.check(TokenType::RightBracket) {
+                    return Err(LoxError::syntax(self.peek(), "Rest pattern must be last in a list pattern."));
+                }
+                break;
+            }
+
+            elements.push(self.pattern()?);
+            if !self.try_match(vec!(TokenType::Comma)) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBracket, "Expect ']' after list pattern.")?;
+        Ok(Pattern::List(elements, rest))
+    }
+
+    fn fields_pattern(&mut self) -> RuntimeError<Pattern> {
+        let mut fields = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let name = self.consume(TokenType::Identifier, "Expect field name in pattern.")?;
+            let pattern = match self.try_match(vec!(TokenType::Colon)) {
+                true => self.pattern()?,
+                false => Pattern::Binding(name.clone())
+            };
+            fields.push((name, pattern));
+
+            if !self.try_match(vec!(TokenType::Comma)) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after field pattern.")?;
+        Ok(Pattern::Fields(fields))
+    }
+
+    //the target of a destructuring var, for loop or parameter, always a list or field pattern
+    fn destructure_pattern(&mut self) -> RuntimeError<Pattern> {
+        if self.try_match(vec!(TokenType::LeftBracket)) {
+            self.list_pattern()
+        }
+        else if self.try_match(vec!(TokenType::LeftBrace)) {
+            self.fields_pattern()
+        }
+        else {
+            Err(LoxError::syntax(self.peek(), "Expect '[' or '{' to destructure."))
+        }
+    }
+
+    //turns a list literal on the left of '=' back into the pattern it spells
+    fn assign_pattern(&self, expr : &dyn Expr) -> Option<Pattern> {
+        if let Some(v) = expr.as_any().downcast_ref::<VarExpr>() {
+            match v.name.lexeme.as_str() {
+                "_" => Some(Pattern::Wildcard),
+                _ => Some(Pattern::Binding(v.name.clone()))
+            }
+        }
+        else if let Some(v) = expr.as_any().downcast_ref::<Index>() {
+            let mut elements = Vec::new();
+            for element in &v.collection {
+                elements.push(self.assign_pattern(element.as_ref())?);
+            }
+            Some(Pattern::List(elements, None))
+        }
+        else {
+            None
+        }
+    }
+
+    //name, then an optional ': type' and '= default', a leading ... makes it collect the rest of the arguments
+    fn parameter(&mut self, previous : &[Parameter]) -> RuntimeError<Parameter> {
+        if previous.last().is_some_and(|p| p.variadic) {
+            return Err(LoxError::syntax(self.peek(), "Variadic parameter must be the last one."));
+        }
+        let variadic = self.try_match(vec!(TokenType::DotDotDot));
+        let (name, pattern) = match variadic {
+            true => (self.consume(TokenType::Identifier, "Expect parameter name after '...'.")?, None),
+            false => self.parameter_name(previous.len())?
+        };
+        
+        let binding = match self.try_match(vec!(TokenType::Colon)) {
+            true => Some(self.type_annotation("Expect type after ':'")?),
+            false => None
+        };
+        
+        let mut parameter = Parameter::new(name, binding);
+        parameter.pattern = pattern;
+        parameter.variadic = variadic;
+        
+        if self.try_match(vec!(TokenType::Equal)) {
+            if variadic {
+                return Err(LoxError::syntax(self.previous(), "Variadic parameter cannot have a default."));
+            }
+            parameter.default = Some(self.expression()?);
+        }
+        else if !variadic && previous.iter().any(|p| p.default.is_some()) {
+            return Err(LoxError::syntax(parameter.name.clone(), "Parameter without a default cannot follow one with a default."));
+        }
+        Ok(parameter)
+    }
+
+    //num, string, bool, fn, map, list or list<T>, or a class name, each optionally followed by ? to allow nil
+    fn type_annotation(&mut self, message : &str) -> RuntimeError<TypeAnnotation> {
+        if !self.try_match(vec!(TokenType::Number, TokenType::Bool, TokenType::String, TokenType::Fn, TokenType::Identifier)) {
+            return Err(LoxError::syntax(self.previous(), message));
+        }
+        let token = self.previous();
+        
+        let kind = match token.type_ {
+            TokenType::Number => TypeKind::Num,
+            TokenType::Bool => TypeKind::Bool,
+            TokenType::String => TypeKind::String,
+            TokenType::Fn => TypeKind::Fn,
+            _ if token.lexeme == "map" => TypeKind::Map,
+            _ if token.lexeme == "list" => {
+                match self.try_match(vec!(TokenType::Less)) {
+                    true => {
+                        let item = self.type_annotation("Expect item type after '<'.")?;
+                        self.consume(TokenType::Greater, "Expect '>' after list item type.")?;
+                        TypeKind::List(Some(Box::new(item)))
+                    },
+                    false => TypeKind::List(None)
+                }
+            },
+            _ => TypeKind::Class(token.lexeme.clone())
+        };
+        
+        let nullable = self.try_match(vec!(TokenType::Question));
+        Ok(TypeAnnotation::new(token, kind, nullable))
+    }
+
+    fn return_type(&mut self) -> RuntimeError<Option<TypeAnnotation>> {
+        match self.try_match(vec!(TokenType::Arrow)) {
+            true => Ok(Some(self.type_annotation("Expect return type after '->'.")?)),
+            false => Ok(None)
+        }
+    }
+
+    //a destructured parameter is still passed positionally, under a name no user code can spell
+    fn parameter_name(&mut self, index : usize) -> RuntimeError<(Token, Option<Pattern>)> {
+        if self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace) {
+            let line = self.peek().line;
+            let pattern = self.destructure_pattern()?;
+            Ok((Token::new(TokenType::Identifier, &format!("@param{}", index), None, line), Some(pattern)))
+        }
+        else {
+            Ok((self.consume(TokenType::Identifier, "Expect parameter name.")?, None))
+        }
+    }
+
     fn block(&mut self) -> RuntimeError<Vec<Box<dyn Stmt>>> {
         let mut statements = Vec::new();
         
@@ -778,17 +1515,45 @@ impl Parser {
         let mut loop_type = LoopType::For;
         let mut initializer : Option<Box<dyn Stmt>> = None;
         let mut initializer2 : Option<Box<dyn Stmt>> = None;
+        let mut initializer_items : Option<Box<dyn Stmt>> = None;
         
         let mut initializer_alt = None;
+        let keyword = self.previous();
+        let mut loop_pattern = None;
+        let mut pairs = false;
         
-        if self.try_match(vec!(TokenType::Var, TokenType::String, TokenType::Bool, TokenType::Number)) {
-            initializer = Some(self.var_declaration(self.previous())?);
+        if self.try_match(vec!(TokenType::Var)) {
+            initializer = Some(self.var_declaration(self.previous(), None, None)?);
+        }
+        else if self.typed_declaration_ahead() {
+            initializer = Some(self.typed_declaration(None)?);
         }
         else if self.try_match(vec!(TokenType::Semicolon)) {
             initializer = None;
         }
+        else if self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace) {
+            //each item lands in a hidden variable that the pattern is then taken apart from
+            loop_pattern = Some(self.destructure_pattern()?);
+            initializer_alt = Some(Token::new(TokenType::Identifier, "@item", None, keyword.line));
+
+            if !self.check(TokenType::In) {
+                return Err(LoxError::syntax(self.peek(), "Expect 'in' after destructuring pattern."));
+            }
+        }
         else {
             initializer_alt = Some(self.consume(TokenType::Identifier, "Expect identifier after for statement.")?);
+            
+            //for k, v in m takes apart [key, value] pairs the same way a list pattern would
+            if self.try_match(vec!(TokenType::Comma)) {
+                let second = self.consume(TokenType::Identifier, "Expect second identifier after ','.")?;
+                loop_pattern = Some(Pattern::List(vec!(Pattern::Binding(initializer_alt.unwrap()), Pattern::Binding(second)), None));
+                initializer_alt = Some(Token::new(TokenType::Identifier, "@item", None, keyword.line));
+                pairs = true;
+                
+                if !self.check(TokenType::In) {
+                    return Err(LoxError::syntax(self.peek(), "Expect 'in' after loop variables."));
+                }
+            }
         }
         
         let mut condition : Option<Box<dyn Expr>> = None;
@@ -850,7 +1615,31 @@ impl Parser {
             loop_type = LoopType::ForEach;
         
             self.consume(TokenType::In, "Expect 'in' after for intializer.")?;
-            let condition_val_alt = self.expression()?;
+            let line = initializer_alt.clone().unwrap().line;
+            
+            //the collection is evaluated once into a hidden variable instead of on every pass
+            let items = Token::new(TokenType::Identifier, &format!("@{}_in", initializer_alt.clone().unwrap().lexeme), None, line);
+            initializer_items = Some(Box::new(Var::new(
+                items.clone(),
+                Token::new(TokenType::Var, "var", None, line),
+                Some(Box::new(Iterable::new(keyword.clone(), self.expression()?, pairs)))
+            )) as Box<dyn Stmt>);
+            let condition_val_alt : Box<dyn Expr> = Box::new(VarExpr::new(items));
+            
+            //#coll > 0 and coll[0], so an empty collection skips the loop instead of failing to index it
+            let first_item : Box<dyn Expr> = Box::new(Logical::new(
+                Box::new(Binary::new(
+                    Box::new(Unary::new(Token::new(TokenType::Hash, "#", None, line), condition_val_alt.clone())),
+                    Token::new(TokenType::Greater, ">", None, line),
+                    Box::new(LiteralExp::new(Some(Literal::Number(0.0))))
+                )),
+                Token::new(TokenType::And, "and", None, line),
+                Box::new(IndexGet::new(
+                    Token::new(TokenType::RightBracket, "]", None, line),
+                    Box::new(LiteralExp::new(Some(Literal::Number(0.0)))),
+                    condition_val_alt.clone()
+                ))
+            ));
             
             initializer = 
             Some(
@@ -862,20 +1651,7 @@ impl Parser {
                         None,
                         initializer_alt.clone().unwrap().line
                     ),
-                    Some(
-                        Box::new(IndexGet::new(
-                            Token::new(
-                                TokenType::RightBracket,
-                                "]",
-                                None,
-                                initializer_alt.clone().unwrap().line
-                            ),
-                            Box::new(LiteralExp::new(
-                                Some(Literal::Number(0.0))
-                            )),
-                            condition_val_alt.clone()
-                        ))
-                    )
+                    Some(first_item)
                 ))
             );
             
@@ -987,6 +1763,11 @@ impl Parser {
         }
 
         let mut body = self.statement()?;
+
+        if let Some(pattern) = loop_pattern {
+            let item = Box::new(VarExpr::new(initializer_alt.clone().unwrap()));
+            body = Box::new(Block::new(vec!(Box::new(Destructure::new(keyword, pattern, item)), body)));
+        }
         
         if let Some(v) = increment {
             if let Some(v2) = increment2 {
@@ -1004,12 +1785,16 @@ impl Parser {
         body = Box::new(While::new(condition.unwrap(), body, loop_type));
         
         if let Some(v) = initializer {
+            let mut statements = Vec::new();
+            if let Some(items) = initializer_items {
+                statements.push(items);
+            }
+            statements.push(v);
             if let Some(v2) = initializer2 {
-                body = Box::new(Block::new(vec!(v, v2, body)));
+                statements.push(v2);
             }
-            else {
-                body = Box::new(Block::new(vec!(v, body)));
-            }    
+            statements.push(body);
+            body = Box::new(Block::new(statements));
         }
 
         Ok(body)
@@ -1023,11 +1808,12 @@ impl Parser {
     }
     
     fn print_statement(&mut self, newline : bool) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.previous();
         self.consume(TokenType::LeftParen, "Expect '(' after 'print'.")?;
         let value = self.expression()?;
         self.consume(TokenType::RightParen, "Expect ')' after 'print'.")?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Box::new(Print::new(value, newline)))
+        Ok(Box::new(Print::new(keyword, value, newline)))
     }
     
     fn return_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
@@ -1042,6 +1828,71 @@ impl Parser {
         Ok(Box::new(Return::new(keyword, value)))
     }
     
+    fn throw_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Box::new(Throw::new(keyword, value)))
+    }
+
+    fn try_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let mut catch = None;
+        if self.try_match(vec!(TokenType::Catch)) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Identifier, "Expect name for the caught value.")?;
+            self.consume(TokenType::RightParen, "Expect ')' after caught value name.")?;
+            self.consume(TokenType::LeftBrace, "Expect '{' after catch.")?;
+            catch = Some((name, self.block()?));
+        }
+
+        let mut finally = None;
+        if self.try_match(vec!(TokenType::Finally)) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            finally = Some(self.block()?);
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(LoxError::syntax(keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Box::new(Try::new(body, catch, finally)))
+    }
+
+    fn module_path(&mut self) -> RuntimeError<Token> {
+        let path = self.consume(TokenType::String, "Expect module path.")?;
+        match path.literal {
+            Some(Literal::String(_)) => Ok(path),
+            _ => Err(LoxError::syntax(path, "Module path must be a string."))
+        }
+    }
+
+    fn import_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.previous();
+        let path = self.module_path()?;
+        let mut alias = None;
+        if self.try_match(vec!(TokenType::As)) {
+            alias = Some(self.consume(TokenType::Identifier, "Expect module name after 'as'.")?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Box::new(Import::new(keyword, path, alias, Vec::new())))
+    }
+
+    fn import_from_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let keyword = self.advance();
+        let path = self.module_path()?;
+        self.consume(TokenType::Import, "Expect 'import' after module path.")?;
+
+        let mut names = vec!(self.consume(TokenType::Identifier, "Expect name to import.")?);
+        while self.try_match(vec!(TokenType::Comma)) {
+            names.push(self.consume(TokenType::Identifier, "Expect name to import.")?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Box::new(Import::new(keyword, path, None, names)))
+    }
+
     fn break_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
         let keyword = self.previous();
         self.consume(TokenType::Semicolon, "Expect ';' after Break.")?;
@@ -1054,6 +1905,15 @@ impl Parser {
         Ok(Box::new(Continue::new(keyword)))
     }
     
+    //{name, age} = person; would read as a block, so it gets a statement of its own
+    fn fields_assignment(&mut self) -> RuntimeError<Box<dyn Stmt>> {
+        let pattern = self.fields_pattern()?;
+        let equals = self.consume(TokenType::Equal, "Expect '=' after field pattern.")?;
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
+        Ok(Box::new(StmtExpr::new(Box::new(AssignPattern::new(equals, pattern, value)))))
+    }
+
     fn expression_statement(&mut self) -> RuntimeError<Box<dyn Stmt>> {
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
@@ -1065,12 +1925,12 @@ impl Parser {
             Ok(self.advance())
         }
         else {
-            Err((self.tokens[self.current as usize].clone(), message.to_string()))
+            Err(LoxError::syntax(self.tokens[self.current as usize].clone(), message))
         }
     }
     
     fn error(&mut self, token : Token, message : &str) {
-        App::error(token, message);
+        self.diagnostics.error(token, message);
     }
     
     fn try_match(&mut self, t : Vec<TokenType>) -> bool {
@@ -1100,6 +1960,13 @@ impl Parser {
         self.tokens.get(self.current as usize).unwrap().clone()
     }
     
+    fn check_next(&self, type_to_check : TokenType) -> bool {
+        match self.tokens.get(self.current as usize + 1) {
+            Some(t) => t.type_ == type_to_check,
+            None => false
+        }
+    }
+    
     fn previous(&self) -> Token {
         self.tokens.get((self.current-1) as usize).unwrap().clone()
     }
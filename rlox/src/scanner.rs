@@ -1,7 +1,11 @@
 use std::collections::HashMap;
+use unicode_ident::{is_xid_start, is_xid_continue};
+
 use crate::types::*;
+use crate::errors::*;
 use crate::tokens::*;
-use crate::application::*;
+use crate::diagnostics::*;
+use crate::highlight::*;
 
 pub struct Scanner {
     pub source : String,
@@ -9,11 +13,23 @@ pub struct Scanner {
     pub start : i32,
     pub current : i32,
     pub line : i32,
+    pub line_start : i32,
+    pub column : i32,
+    pub start_line : i32,
+    pub start_column : i32,
+    pub file : usize,
+    pub diagnostics : Diagnostics,
+    pub interpolations : Vec<(Token, i32)>,
+    pub keep_comments : bool,
     pub keywords : HashMap<String, TokenType>
 }
 
 impl Scanner {
     pub fn new(source : String) -> Self {
+        Self::new_with_file(source, 0)
+    }
+
+    pub fn new_with_file(source : String, file : usize) -> Self {
         let mut keywords_container = HashMap::new();
         keywords_container.insert(String::from("&&"), TokenType::And);
         keywords_container.insert(String::from("class"), TokenType::Class);
@@ -41,6 +57,17 @@ impl Scanner {
         keywords_container.insert(String::from("as"), TokenType::As);
         keywords_container.insert(String::from("is"), TokenType::Is);
         keywords_container.insert(String::from("in"), TokenType::In);
+        keywords_container.insert(String::from("match"), TokenType::Match);
+        keywords_container.insert(String::from("enum"), TokenType::Enum);
+        keywords_container.insert(String::from("interface"), TokenType::Interface);
+        keywords_container.insert(String::from("impl"), TokenType::Impl);
+        keywords_container.insert(String::from("abstract"), TokenType::Abstract);
+        keywords_container.insert(String::from("static"), TokenType::Static);
+        keywords_container.insert(String::from("throw"), TokenType::Throw);
+        keywords_container.insert(String::from("try"), TokenType::Try);
+        keywords_container.insert(String::from("catch"), TokenType::Catch);
+        keywords_container.insert(String::from("finally"), TokenType::Finally);
+        keywords_container.insert(String::from("import"), TokenType::Import);
     
         Self {
             source,
@@ -48,6 +75,14 @@ impl Scanner {
             start : 0,
             current : 0,
             line : 1,
+            line_start : 0,
+            column : 0,
+            start_line : 1,
+            start_column : 1,
+            file,
+            diagnostics : Diagnostics::new(),
+            interpolations : Vec::new(),
+            keep_comments : false,
             keywords : keywords_container
         }
     }
@@ -55,26 +90,86 @@ impl Scanner {
     pub fn scan_tokens(&mut self) -> RuntimeError<&Vec<Token>> {
         while !self.is_at_end() {
             self.start = self.current;
-            self.scan_token()?;
+            self.start_line = self.line;
+            self.start_column = self.column + 1;
+            if let Err(e) = self.scan_token() {
+                self.diagnostics.push(e);
+            }
         }
         
-        self.tokens.push(Token::new(TokenType::Eof, "", None, self.line));
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+        if let Some((opener, _)) = self.interpolations.first() {
+            self.diagnostics.error(opener.clone(), "Unterminated string interpolation.");
+        }
+        let eof = Token::new_with_span(TokenType::Eof, "", None, self.span());
+        self.tokens.push(eof);
         
-        Ok(&self.tokens)
+        match self.diagnostics.first() {
+            Some(e) => Err(e),
+            None => Ok(&self.tokens)
+        }
     }
     
     pub fn scan_token(&mut self) -> RuntimeError<()> {
         match self.advance()? {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            },
+            '}' => {
+                match self.interpolations.last_mut() {
+                    Some((_, 0)) => {
+                        //closes a ${ } so the rest of the string literal picks up from here
+                        self.interpolations.pop();
+                        self.string()?
+                    },
+                    Some((_, depth)) => {
+                        *depth -= 1;
+                        self.add_token(TokenType::RightBrace)
+                    },
+                    None => self.add_token(TokenType::RightBrace)
+                }
+            },
             '[' => self.add_token(TokenType::LeftBracket),
             ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
+            '.' => {
+                if self.try_pair('.') {
+                    if self.try_pair('.') {
+                        self.add_token(TokenType::DotDotDot)
+                    }
+                    else if self.try_pair('=') {
+                        self.add_token(TokenType::DotDotEqual)
+                    }
+                    else {
+                        self.add_token(TokenType::DotDot)
+                    }
+                }
+                else {
+                    self.add_token(TokenType::Dot)
+                }
+            },
             '#' => self.add_token(TokenType::Hash),
-            '?' => self.add_token(TokenType::Question),
+            '?' => {
+                if self.try_pair('?') {
+                    match self.try_pair('=') {
+                        true => self.add_token(TokenType::QuestionQuestionEqual),
+                        false => self.add_token(TokenType::QuestionQuestion)
+                    }
+                }
+                else if self.try_pair('.') {
+                    self.add_token(TokenType::QuestionDot)
+                }
+                else {
+                    self.add_token(TokenType::Question)
+                }
+            },
             '|' => self.add_token(TokenType::Pipe),
             '-' => {
                 if self.try_pair('-') {
@@ -83,6 +178,9 @@ impl Scanner {
                 else if self.try_pair('=') {
                     self.add_token(TokenType::MinusEqual)
                 }
+                else if self.try_pair('>') {
+                    self.add_token(TokenType::Arrow)
+                }
                 else {
                     self.add_token(TokenType::Minus)
                 }
@@ -142,27 +240,46 @@ impl Scanner {
                 }
             },
             '/' => {
-                match self.try_pair('/') {
-                    true => {
+                if self.try_pair('/') {
+                    if self.peek() == '/' && self.peek_next() != '/' {
+                        self.doc_comment()?;
+                    }
+                    else {
                         while self.peek() != '\n' && !self.is_at_end() {
                             self.advance()?;
                         }
-                    },
-                    false => {
-                        match self.try_pair('=') {
-                            true => self.add_token(TokenType::SlashEqual),
-                            false => self.add_token(TokenType::Slash)
-                        }
+                        self.add_comment();
                     }
                 }
+                else if self.try_pair('*') {
+                    self.block_comment()?;
+                    self.add_comment();
+                }
+                else if self.try_pair('=') {
+                    self.add_token(TokenType::SlashEqual)
+                }
+                else {
+                    self.add_token(TokenType::Slash)
+                }
             }
             ' ' | '\r' | '\t' => { },
-            '\n' => self.line += 1,
-            '"' => self.string()?,
+            '\n' => self.new_line(),
+            '"' => {
+                if self.starts_with("\"\"") {
+                    self.advance()?;
+                    self.advance()?;
+                    self.triple_string()?
+                }
+                else {
+                    self.string()?
+                }
+            },
+            'r' if self.raw_string_ahead() => self.raw_string()?,
             v if Scanner::is_digit(v) => self.number()?,
             v if Scanner::is_alpha(v) => self.identifier()?,
             _ => {
-                App::error_at(self.line,  "Unexpected character.")
+                let token = self.error_token();
+                self.diagnostics.error(token, "Unexpected character.")
             }
         };
         
@@ -170,17 +287,16 @@ impl Scanner {
     }
     
     pub fn is_digit(c : char) -> bool {
-        c >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
     
+    //identifiers follow the unicode XID rules, plus a leading '_'
     pub fn is_alpha(c : char) -> bool {
-        (c >= 'a' && c <= 'z') ||
-        (c >= 'A' && c <= 'Z') ||
-        (c == '_')
+        c == '_' || is_xid_start(c)
     }
     
     pub fn is_alpha_numeric(c : char) -> bool {
-        Scanner::is_digit(c) || Scanner::is_alpha(c)
+        is_xid_continue(c)
     }
     
     pub fn identifier(&mut self) -> RuntimeError<()> {
@@ -188,110 +304,472 @@ impl Scanner {
             self.advance()?;
         }
         
-        let value = String::from(&self.source.as_str()[self.start as usize..self.current as usize]);
-        let type_ = match self.keywords.get(&value) {
-            Some(v) => v,
-            None => &TokenType::Identifier
+        let value = &self.source.as_str()[self.start as usize..self.current as usize];
+        let type_ = match self.keywords.get(value) {
+            Some(v) => *v,
+            None => TokenType::Identifier
         };
         
-        self.add_token(*type_);
+        self.add_token(type_);
         
         Ok(())
     }
     
+    //a malformed literal still becomes a Number token so the parser does not report it twice
     pub fn number(&mut self) -> RuntimeError<()>  {
-        while Scanner::is_digit(self.peek()) {
-            self.advance()?;
+        let value = match self.number_value() {
+            Ok(v) => v,
+            Err(e) => {
+                self.diagnostics.push(e);
+                0.0
+            }
+        };
+
+        self.add_token_with_literal(TokenType::Number, Some(Literal::Number(value)));
+        Ok(())
+    }
+
+    fn number_value(&mut self) -> RuntimeError<f64> {
+        let first = self.source.as_bytes()[self.start as usize] as char;
+        let radix = match (first, self.peek()) {
+            ('0', 'x') | ('0', 'X') => Some((16, "hex")),
+            ('0', 'b') | ('0', 'B') => Some((2, "binary")),
+            ('0', 'o') | ('0', 'O') => Some((8, "octal")),
+            _ => None
+        };
+
+        match radix {
+            Some((radix, name)) => {
+                self.advance()?;
+                let digits = self.digits(radix, String::new())?;
+                if digits.is_empty() {
+                    self.suffix()?;
+                    return Err(LoxError::syntax(self.error_token(), &format!("Expect {} digits after '{}'.", name, &self.source[self.start as usize..(self.start+2) as usize])));
+                }
+                if Scanner::is_alpha_numeric(self.peek()) {
+                    self.suffix()?;
+                    return Err(LoxError::syntax(self.error_token(), &format!("Invalid digit in {} literal.", name)));
+                }
+
+                match u64::from_str_radix(&digits, radix) {
+                    Ok(v) => Ok(v as f64),
+                    Err(_) => Err(LoxError::syntax(self.error_token(), "Number literal is too large."))
+                }
+            },
+            None => {
+                let mut text = self.digits(10, String::from(first))?;
+
+                if self.peek() == '.' && Scanner::is_digit(self.peek_next()) {
+                    self.advance()?;
+                    text.push('.');
+                    text = self.digits(10, text)?;
+                }
+
+                if self.peek() == 'e' || self.peek() == 'E' {
+                    self.advance()?;
+                    text.push('e');
+                    if self.peek() == '+' || self.peek() == '-' {
+                        text.push(self.advance()?);
+                    }
+
+                    let exponent = self.digits(10, String::new())?;
+                    if exponent.is_empty() {
+                        self.suffix()?;
+                        return Err(LoxError::syntax(self.error_token(), "Expect digits in exponent."));
+                    }
+                    text.push_str(&exponent);
+                }
+
+                if self.peek() == '_' {
+                    self.suffix()?;
+                    return Err(LoxError::syntax(self.error_token(), "Digit separator '_' must be between digits."));
+                }
+                if Scanner::is_alpha_numeric(self.peek()) {
+                    self.suffix()?;
+                    return Err(LoxError::syntax(self.error_token(), "Invalid suffix on number literal."));
+                }
+
+                match text.parse::<f64>() {
+                    Ok(v) => Ok(v),
+                    Err(_) => Err(LoxError::syntax(self.error_token(), "Could not parse num."))
+                }
+            }
         }
-        
-        if self.peek() == '.' && Scanner::is_digit(self.peek_next()) {
-            self.advance()?;
-            
-            while Scanner::is_digit(self.peek()) {
+    }
+
+    //digits of the given radix, a '_' separator is only allowed between two digits
+    fn digits(&mut self, radix : u32, mut digits : String) -> RuntimeError<String> {
+        loop {
+            let c = self.peek();
+            let after_digit = digits.chars().last().is_some_and(|d| d.is_digit(radix));
+            if c.is_digit(radix) {
+                digits.push(self.advance()?);
+            }
+            else if c == '_' && after_digit && self.peek_next().is_digit(radix) {
                 self.advance()?;
             }
+            else {
+                return Ok(digits);
+            }
         }
-        
-        let num = &self.source.as_str()[self.start as usize..self.current as usize].parse::<f64>();
-        
-        match num {
-            Ok(val) => {
-                self.add_token_with_literal(TokenType::Number, Some(Literal::Number(*val)));
-                Ok(())
-            },
-            Err(_) => Err((self.tokens[self.current as usize].clone(), "Could not parse num.".to_string()))
+    }
+
+    //swallows the rest of a malformed literal so it is reported once
+    fn suffix(&mut self) -> RuntimeError<()> {
+        while Scanner::is_alpha_numeric(self.peek()) {
+            self.advance()?;
         }
+        Ok(())
     }
      
+    //the text after /// becomes the literal, one leading space is dropped
+    fn doc_comment(&mut self) -> RuntimeError<()> {
+        self.advance()?;
+        let mut text = String::new();
+        while self.peek() != '\n' && !self.is_at_end() {
+            text.push(self.advance()?);
+        }
+
+        let text = text.strip_prefix(' ').unwrap_or(&text).trim_end().to_string();
+        self.add_token_with_literal(TokenType::DocComment, Some(Literal::String(text)));
+        Ok(())
+    }
+
+    //block comments nest, so commenting out code that already has one still works
+    fn block_comment(&mut self) -> RuntimeError<()> {
+        let opener = Span::new(self.file, self.start as usize, (self.start+2) as usize, self.start_line, self.start_column as usize);
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.is_at_end() {
+                let token = Token::new_with_span(TokenType::Eof, "/*", None, opener);
+                return Err(LoxError::syntax(token, "Unterminated block comment."));
+            }
+
+            match self.advance()? {
+                '/' if self.peek() == '*' => {
+                    self.advance()?;
+                    depth += 1;
+                },
+                '*' if self.peek() == '/' => {
+                    self.advance()?;
+                    depth -= 1;
+                },
+                '\n' => self.new_line(),
+                _ => { }
+            }
+        }
+
+        Ok(())
+    }
+
+    //a string ending in ${ becomes an Interpolation token, the parser stitches the parts back together
     pub fn string(&mut self) -> RuntimeError<()> {
+        let mut value = String::new();
+
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+            match self.advance()? {
+                '\n' => {
+                    value.push('\n');
+                    self.new_line();
+                },
+                '\\' => {
+                    match self.escape() {
+                        Ok(c) => value.push(c),
+                        Err(e) => self.diagnostics.push(e)
+                    }
+                },
+                '$' if self.peek() == '{' => {
+                    self.advance()?;
+                    self.add_token_with_literal(TokenType::Interpolation, Some(Literal::String(value)));
+                    let opener = self.token_from(self.current-2);
+                    self.interpolations.push((opener, 0));
+                    return Ok(());
+                },
+                c => value.push(c)
             }
-            
-            self.advance()?;
         }
         
         if self.is_at_end() {
-            Err((self.tokens[self.current as usize].clone(), "Unterminated String.".to_string()))
+            Err(LoxError::syntax(self.error_token(), "Unterminated String."))
         }
         else {
             self.advance()?;
-            
-            let value = String::from(&self.source.as_str()[(self.start+1) as usize..(self.current-1) as usize]);
             self.add_token_with_literal(TokenType::String, Some(Literal::String(value)));
             
             Ok(())
         }
     }
-    
-    pub fn peek(&self) -> char {
+
+    //r"..." or r#"..."#, no escapes or interpolation, the hashes let the text contain quotes
+    fn raw_string(&mut self) -> RuntimeError<()> {
+        let mut hashes = 0;
+        while self.try_pair('#') {
+            hashes += 1;
+        }
+        self.advance()?;
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let mut value = String::new();
+
+        while !self.starts_with(&closing) {
+            if self.is_at_end() {
+                return Err(LoxError::syntax(self.error_token(), "Unterminated raw string."));
+            }
+
+            let c = self.advance()?;
+            value.push(c);
+            if c == '\n' {
+                self.new_line();
+            }
+        }
+
+        for _ in 0..closing.len() {
+            self.advance()?;
+        }
+        self.add_token_with_literal(TokenType::String, Some(Literal::String(value)));
+        Ok(())
+    }
+
+    fn raw_string_ahead(&self) -> bool {
+        self.rest().find(|c| *c != '#') == Some('"')
+    }
+
+    //"""...""" keeps line breaks and strips the indentation shared by its lines,
+    //the line breaks right after the opening quotes and before the closing ones are dropped
+    fn triple_string(&mut self) -> RuntimeError<()> {
+        //each line keeps its text and where its first non-whitespace character is, None while blank
+        let mut lines : Vec<(String, Option<usize>)> = vec!((String::new(), None));
+
+        while !self.starts_with("\"\"\"") {
+            if self.is_at_end() {
+                return Err(LoxError::syntax(self.error_token(), "Unterminated String."));
+            }
+
+            let c = match self.advance()? {
+                '\n' => {
+                    self.new_line();
+                    lines.push((String::new(), None));
+                    continue;
+                },
+                '\\' => {
+                    match self.escape() {
+                        Ok(c) => c,
+                        Err(e) => {
+                            self.diagnostics.push(e);
+                            continue;
+                        }
+                    }
+                },
+                c if c == ' ' || c == '\t' => {
+                    lines.last_mut().unwrap().0.push(c);
+                    continue;
+                },
+                c => c
+            };
+
+            let (text, indent) = lines.last_mut().unwrap();
+            if indent.is_none() {
+                *indent = Some(text.chars().count());
+            }
+            text.push(c);
+        }
+
+        for _ in 0..3 {
+            self.advance()?;
+        }
+
+        //text on the opening line has no indentation of its own so it is kept as written
+        let first = lines.remove(0);
+        let first = match first.1.is_some() || lines.is_empty() {
+            true => Some(first.0),
+            false => None
+        };
+        if lines.last().is_some_and(|l| l.1.is_none()) {
+            lines.pop();
+        }
+
+        let strip = lines.iter().filter_map(|l| l.1).min().unwrap_or(0);
+        let body = lines.into_iter().map(|(text, indent)| match indent {
+            Some(_) => text.chars().skip(strip).collect(),
+            None => String::new()
+        });
+        let value = first.into_iter().chain(body).collect::<Vec<String>>().join("\n");
+
+        self.add_token_with_literal(TokenType::String, Some(Literal::String(value)));
+        Ok(())
+    }
+
+    fn escape(&mut self) -> RuntimeError<char> {
+        let at = self.current-1;
+
         if self.is_at_end() {
-            '\0'
+            return Err(LoxError::syntax(self.error_token(), "Unterminated String."));
         }
-        else {
-            self.source.chars().nth(self.current as usize).unwrap()
+
+        match self.advance()? {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            '\\' => Ok('\\'),
+            '$' => Ok('$'),
+            'u' => {
+                if !self.try_pair('{') {
+                    return Err(self.escape_error(at, "Expect '{' after '\\u'."));
+                }
+
+                let mut digits = String::new();
+                while self.peek().is_ascii_hexdigit() && digits.len() < 6 {
+                    digits.push(self.advance()?);
+                }
+
+                if digits.is_empty() || !self.try_pair('}') {
+                    return Err(self.escape_error(at, "Invalid unicode escape, expect 1 to 6 hex digits in '\\u{...}'."));
+                }
+
+                match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                    Some(c) => Ok(c),
+                    None => Err(self.escape_error(at, "Invalid unicode code point."))
+                }
+            },
+            c => Err(self.escape_error(at, &format!("Invalid escape sequence '\\{}'.", c)))
         }
     }
+
+    //points at the escape itself rather than the whole string literal
+    fn escape_error(&self, at : i32, message : &str) -> LoxError {
+        LoxError::syntax(self.token_from(at), message)
+    }
+
+    //token covering at..current on the current line, only built on error paths so counting columns here is fine
+    fn token_from(&self, at : i32) -> Token {
+        let text = self.source.get(at as usize..self.current as usize).unwrap_or("");
+        let column = self.source.get(self.line_start as usize..at as usize).map_or(0, |l| l.chars().count());
+        let span = Span::new(
+            self.file,
+            at as usize,
+            self.current as usize,
+            self.line,
+            column + 1
+        );
+        Token::new_with_span(TokenType::Eof, text, None, span)
+    }
+    
+    //current is a byte offset, so looking ahead only decodes the next char or two
+    fn rest(&self) -> std::str::Chars<'_> {
+        self.source.get(self.current as usize..).unwrap_or("").chars()
+    }
+
+    fn starts_with(&self, text : &str) -> bool {
+        self.source.get(self.current as usize..).unwrap_or("").starts_with(text)
+    }
+
+    pub fn peek(&self) -> char {
+        self.rest().next().unwrap_or('\0')
+    }
     
     pub fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() as i32 {
-            '\0'
-        }
-        else {
-            self.source.chars().nth((self.current+1) as usize).unwrap()
-        }
+        self.rest().nth(1).unwrap_or('\0')
     }
     
     pub fn try_pair(&mut self, expected : char) -> bool {
-        if self.is_at_end() || 
-        self.source.chars().nth(self.current as usize).unwrap() != expected {
+        if self.is_at_end() || self.peek() != expected {
             false
         }
         else {
-            self.current += 1;
+            self.current += expected.len_utf8() as i32;
+            self.column += 1;
             
             true
         }
     }
      
     pub fn advance(&mut self) -> RuntimeError<char> {
-        let current_char = self.source.chars().nth(self.current as usize);
-        self.current += 1;
-    
-        match current_char {
-            Some(v) => Ok(v),
-            None => Err((self.tokens[self.current as usize].clone(), "Character not found.".to_string()))
+        match self.rest().next() {
+            Some(v) => {
+                self.current += v.len_utf8() as i32;
+                self.column += 1;
+                Ok(v)
+            },
+            None => Err(LoxError::syntax(self.error_token(), "Character not found."))
         }
     }
     
+    //plain comments only reach the token stream for tooling such as the highlighter
+    fn add_comment(&mut self) {
+        if self.keep_comments {
+            self.add_token(TokenType::Comment);
+        }
+    }
+
     fn add_token(&mut self, type_ : TokenType) {
         self.add_token_with_literal(type_, None);
     }
     
     fn add_token_with_literal(&mut self, type_ : TokenType, literal : Option<Literal>) {
         let text = &self.source.as_str()[self.start as usize..self.current as usize];
-        self.tokens.push(Token::new(type_, text, literal, self.line));
+        let span = self.span();
+        self.tokens.push(Token::new_with_span(type_, text, literal, span));
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+        self.column = 0;
+    }
+
+    fn span(&self) -> Span {
+        Span::new(
+            self.file,
+            self.start as usize,
+            self.current as usize,
+            self.start_line,
+            self.start_column as usize
+        )
+    }
+
+    //token covering whatever has been scanned so far, used to point errors at the source
+    fn error_token(&self) -> Token {
+        let end = std::cmp::min(self.current, self.source.len() as i32);
+        let text = self.source.get(self.start as usize..end as usize).unwrap_or("");
+        Token::new_with_span(TokenType::Eof, text, None, self.span())
+    }
+    
+    //token stream with kinds and positions plus any scan errors, as a JSON object
+    pub fn tokens_json(&self) -> String {
+        let tokens : Vec<String> = self.tokens.iter().map(|t| {
+            let mut o = format!(
+                "{{\"kind\":{},\"class\":{},\"lexeme\":{},\"line\":{},\"column\":{},\"start\":{},\"end\":{}",
+                json_string(&format!("{:?}", t.type_)),
+                json_string(TokenClass::of(t).name()),
+                json_string(&t.lexeme),
+                t.span.line,
+                t.span.column,
+                t.span.start,
+                t.span.end
+            );
+            match &t.literal {
+                Some(Literal::String(s)) => o.push_str(&format!(",\"value\":{}", json_string(s))),
+                Some(Literal::Number(n)) if n.is_finite() => o.push_str(&format!(",\"value\":{}", n)),
+                _ => { }
+            }
+            o.push('}');
+            o
+        }).collect();
+
+        let errors : Vec<String> = self.diagnostics.errors.iter().map(|e| {
+            format!(
+                "{{\"line\":{},\"column\":{},\"message\":{}}}",
+                e.token().line,
+                e.span().column,
+                json_string(e.message())
+            )
+        }).collect();
+
+        format!("{{\"tokens\":[{}],\"errors\":[{}]}}", tokens.join(","), errors.join(","))
     }
     
     fn is_at_end(&self) -> bool {
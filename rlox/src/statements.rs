@@ -5,13 +5,15 @@ use crate::types::*;
 
 #[derive(Clone, Debug)]
 pub struct Print {
+    pub keyword : Token,
     pub expression : Box<dyn Expr>,
     pub newline : bool
 }
 
 impl Print {
-    pub fn new(expression : Box<dyn Expr>, newline : bool) -> Self {
+    pub fn new(keyword : Token, expression : Box<dyn Expr>, newline : bool) -> Self {
         Self {
+            keyword,
             expression,
             newline
         }
@@ -59,6 +61,59 @@ impl Continue {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct Throw {
+    pub keyword : Token,
+    pub value : Box<dyn Expr>,
+}
+
+impl Throw {
+    pub fn new(keyword : Token, value : Box<dyn Expr>) -> Self {
+        Self {
+            keyword,
+            value
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Try {
+    pub body : Vec<Box<dyn Stmt>>,
+    //the name the caught value is bound to and the block run with it, absent when there's only a finally
+    pub catch : Option<(Token, Vec<Box<dyn Stmt>>)>,
+    pub finally : Option<Vec<Box<dyn Stmt>>>
+}
+
+impl Try {
+    pub fn new(body : Vec<Box<dyn Stmt>>, catch : Option<(Token, Vec<Box<dyn Stmt>>)>, finally : Option<Vec<Box<dyn Stmt>>>) -> Self {
+        Self {
+            body,
+            catch,
+            finally
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub keyword : Token,
+    pub path : Token,
+    pub alias : Option<Token>,
+    //from "x" import a, b binds these directly instead of a namespace
+    pub names : Vec<Token>
+}
+
+impl Import {
+    pub fn new(keyword : Token, path : Token, alias : Option<Token>, names : Vec<Token>) -> Self {
+        Self {
+            keyword,
+            path,
+            alias,
+            names
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct If {
     pub condition : Box<dyn Expr>,
@@ -100,7 +155,17 @@ impl Block {
 pub struct Class {
     pub name : Token,
     pub methods : Vec<Box<dyn Stmt>>,
-    pub super_class : Option<Box<dyn Expr>>
+    pub super_class : Option<Box<dyn Expr>>,
+    pub doc : Option<String>,
+    //everything after 'impl'
+    pub interfaces : Vec<Box<dyn Expr>>,
+    //methods declared without a body, a subclass has to give them one
+    pub abstracts : Vec<Function>,
+    pub statics : Vec<Box<dyn Stmt>>,
+    //class-level fields, shared by the class and every instance of it
+    pub fields : Vec<(Token, Option<Box<dyn Expr>>)>,
+    pub getters : Vec<Function>,
+    pub setters : Vec<Function>
 }
 
 impl Class {
@@ -108,7 +173,32 @@ impl Class {
         Self { 
             name,
             methods,
-            super_class
+            super_class,
+            doc : None,
+            interfaces : Vec::new(),
+            abstracts : Vec::new(),
+            statics : Vec::new(),
+            fields : Vec::new(),
+            getters : Vec::new(),
+            setters : Vec::new()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Interface {
+    pub name : Token,
+    //signatures only, the bodies are always empty
+    pub methods : Vec<Function>,
+    pub doc : Option<String>
+}
+
+impl Interface {
+    pub fn new(name : Token, methods : Vec<Function>) -> Self {
+        Self {
+            name,
+            methods,
+            doc : None
         }
     }
 }
@@ -131,6 +221,9 @@ pub struct Var {
     pub name : Token,
     pub binding : Token,
     pub initializer : Option<Box<dyn Expr>>,
+    pub doc : Option<String>,
+    //set for typed declarations like list<num> xs or Point? p
+    pub annotation : Option<TypeAnnotation>
 }
 
 impl Var {
@@ -138,7 +231,9 @@ impl Var {
         Self {
             name,
             binding,
-            initializer
+            initializer,
+            doc : None,
+            annotation : None
         }
     }
 }
@@ -164,7 +259,10 @@ impl While {
 pub struct Function {
     pub name : Box<Token>,
     pub params : Vec<Parameter>,
-    pub body : Vec<Box<dyn Stmt>>
+    pub body : Vec<Box<dyn Stmt>>,
+    pub doc : Option<String>,
+    //the type after '->', checked against whatever the call hands back
+    pub returns : Option<TypeAnnotation>
 }
 
 impl Function {
@@ -172,11 +270,99 @@ impl Function {
         Self {
             name : Box::new(name),
             params,
+            body,
+            doc : None,
+            returns : None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Wildcard,
+    Literal(Option<Literal>),
+    Binding(Token),
+    //num, string or bool with an optional name for the matched value
+    Type(Token, Option<Token>),
+    Class(Token, Option<Token>),
+    //the rest is None without a ..., Some(None) for a bare ... and Some(Some(name)) for ...name
+    List(Vec<Pattern>, Option<Option<Token>>),
+    //each field name paired with the pattern its value is matched against, {name} is short for {name: name}
+    Fields(Vec<(Token, Pattern)>),
+    //Enum.Variant with patterns for the values it carries, no patterns matches the variant whatever it holds
+    Variant(Token, Token, Vec<Pattern>),
+    Or(Vec<Pattern>),
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchArm {
+    pub pattern : Pattern,
+    pub guard : Option<Box<dyn Expr>>,
+    pub body : Box<dyn Stmt>
+}
+
+impl MatchArm {
+    pub fn new(pattern : Pattern, guard : Option<Box<dyn Expr>>, body : Box<dyn Stmt>) -> Self {
+        Self {
+            pattern,
+            guard,
             body
         }
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct Match {
+    pub keyword : Token,
+    pub value : Box<dyn Expr>,
+    pub arms : Vec<MatchArm>
+}
+
+impl Match {
+    pub fn new(keyword : Token, value : Box<dyn Expr>, arms : Vec<MatchArm>) -> Self {
+        Self {
+            keyword,
+            value,
+            arms
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Enum {
+    pub name : Token,
+    //each variant with the names of the values it carries, empty for a plain one like Red
+    pub variants : Vec<(Token, Vec<Token>)>,
+    pub doc : Option<String>
+}
+
+impl Enum {
+    pub fn new(name : Token, variants : Vec<(Token, Vec<Token>)>) -> Self {
+        Self {
+            name,
+            variants,
+            doc : None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Destructure {
+    pub keyword : Token,
+    pub pattern : Pattern,
+    pub value : Box<dyn Expr>
+}
+
+impl Destructure {
+    pub fn new(keyword : Token, pattern : Pattern, value : Box<dyn Expr>) -> Self {
+        Self {
+            keyword,
+            pattern,
+            value
+        }
+    }
+}
+
 impl Stmt for StmtExpr {
     fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>)  -> RuntimeError<Option<Literal>> {
         visitor.visit_expression_stmt(self)
@@ -327,4 +513,102 @@ impl Stmt for Continue {
     fn as_any(&self) -> &dyn Any {
         self
     }
-}
\ No newline at end of file
+}
+
+impl Stmt for Match {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_match_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Enum {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_enum_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Throw {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_throw_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Try {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_try_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Import {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_import_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Interface {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_interface_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Stmt for Destructure {
+    fn accept(&self, visitor : &mut Box<&mut dyn StmtVisitor>) -> RuntimeError<Option<Literal>> {
+        visitor.visit_destructure_stmt(self)
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn Stmt> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use crate::types::*;
+use crate::errors::*;
 use crate::tokens::*;
 use crate::interpreter::*;
 
@@ -30,8 +31,8 @@ macro_rules! native_function {
                     Token::new(TokenType::Identifier, $s, None, 0)
                 }
 
-                fn arity(&self) -> usize {
-                    $arity as usize
+                fn arity(&self) -> Arity {
+                    Arity::exact($arity as usize)
                 }
                 
                 $($fun)*
@@ -48,154 +49,143 @@ macro_rules! native_function {
     }
 }
 
-pub const STD_LIB_SCRIPT: &str = "
-    class Entry {
-        Entry(key, val) {
-            this.key = key;
-            this.val = val;
-            this.next = nil;
+//kept apart from the rest so App can declare it on its own, see add_std_lib
+pub const ERROR_SCRIPT: &str = "
+    /// What catch receives for errors raised by the interpreter, scripts can throw it or subclass it and call super.Error(message, kind) from the initializer.
+    class Error {
+        /// Creates an error, kind names what went wrong and line is filled in by throw when left out.
+        Error(message, kind = \"Error\", line = nil) {
+            this.message = message;
+            this.kind = kind;
+            this.line = line;
+        }
+
+        /// Shown as kind: message.
+        __str__() {
+            return \"${this.kind}: ${this.message}\";
         }
     }
+";
 
+pub const STD_LIB_SCRIPT: &str = "
+    /// Hash table kept for older scripts, new code can use a map literal directly.
     class Hashmap {
+        /// Creates an empty map.
         Hashmap() {
-            this.buckets = [];
-            for i < 16 {
-                this.buckets += nil;
-            }
+            this.entries = {};
             this.size = 0;
-            this.capacity = 16;
         }
 
-        resize() {
-            var new_capacity = this.capacity*2;
-            var new_table = [];
-            for i < this.capacity {
-                new_table += nil;
-            }
-            for i < this.capacity {
-                var node = new_table[i];
-                while node != null {
-                    var next = node.next;
-                    var index = hashcode(key)%this.capacity;
-                    node.next = new_table[index];
-                    new_table[index] = node;
-                    node = next;
-                }
-            }
-
-            this.buckets = new_table;
-            this.capacity = new_capacity;
-        }
-        
+        /// Adds key with value, replacing the value if key is already present.
         insert(key, value) {
-            var new_entry = Entry(key, value);
-            var hash = hashcode(key)%this.capacity;
-
-            if this.buckets[hash] == nil {
-                this.buckets[hash] = new_entry;
-            }
-            else {
-                var head = this.buckets[hash];
-                this.buckets[hash] = head;
-
-                while head != nil {
-                    if head.key == key {
-                        head.value = value;
-                        return nil;
-                    }
-                    head = head.next;
-                }
-                new_entry.next = this.buckets[hash];
-                this.buckets[hash] = new_entry;
-                this.size++;
-                if this.size > this.capacity * 0.75 {
-                    this.resize();
-                }
-            }
+            this.entries[key] = value;
+            this.size = #this.entries;
         }
 
+        /// Removes key and its value if present.
         remove(key) {
-            var index = hashcode(key)%this.capacity;
-            var node = this.buckets[index];
-            var prev = nil;
-            while node != nil {
-                if node.key == key {
-                    if prev == nil {
-                        this.buckets[index] = node.next;
-                    }
-                    else {
-                        prev.next = node.next;
-                    }
-                    this.size = (this.size-1 < 0) ? 0 : this.size-1;
-                    return;
-                }
-                prev = node;
-                node = node.next;
-            }
+            this.entries -= key;
+            this.size = #this.entries;
         }
 
+        /// Returns the value stored for key, or nil.
         get(key) {
-            var hash = hashcode(key)%this.capacity;
-            var head = this.buckets[hash];
-
-            while head != nil {
-                if head.key == key {
-                    break;
-                    return head.val;
-                }
-                head = head.next;
+            if key in this.entries {
+                return this.entries[key];
             }
-
             return nil;
         }
+
+        /// h[key] reads like get.
+        __index__(key) {
+            return this.get(key);
+        }
+
+        /// h[key] = value stores like insert.
+        __setindex__(key, value) {
+            this.insert(key, value);
+        }
+
+        /// #h is the number of keys.
+        __len__() {
+            return this.size;
+        }
     }
 
+    /// Last in, first out collection.
     class Stack {
+        /// Creates an empty stack.
         Stack() {
             this.items = [];
         }
 
+        /// Puts item on top of the stack.
         push(item) {
             this.items += item;
         }
 
+        /// Removes and returns the top item.
         pop() {
             var item = this.items[-1];
             this.items -= -1;
             return item;
         }
 
+        /// Number of items on the stack.
         count() {
             return #this.items;
         }
+
+        /// #s is the number of items.
+        __len__() {
+            return #this.items;
+        }
     }
 
+    /// First in, first out collection.
     class Queue {
+        /// Creates an empty queue.
         Queue() {
             this.items = [];
         }
 
+        /// Returns the oldest item without removing it.
         front() {
             return this.items[0];
         }
 
+        /// Returns the newest item without removing it.
         back() {
             return this.items[-1];
         }
 
+        /// Adds item to the back of the queue.
         enqueue(item) {
             this.items += item;
         }
 
+        /// Removes and returns the oldest item.
         dequeue() {
             var ret = this.items[0];
             this.items -= 0;
             return ret;
         }
+
+        /// #q is the number of items.
+        __len__() {
+            return #this.items;
+        }
     }
 ";
 
+//indents each line of a doc comment under the signature it documents
+fn indent_doc(doc : Option<String>, indent : &str) -> String {
+    match doc {
+        Some(d) => d.lines().map(|l| format!("{}{}\n", indent, l)).collect(),
+        None => String::new()
+    }
+}
+
 native_function! {
     DebugFunction "debug", 1 => {
         fn call(&self, _interpreter : &mut Interpreter, _callee : Token, arguments : Vec<Option<Literal>>, _auto_clean : bool) -> RuntimeError<Option<Literal>> {
@@ -257,8 +247,37 @@ native_function! {
                     x.hash(&mut s);
                     Ok(Some(Literal::Number(s.finish() as f64)))
                 }
-                _ => Err((callee.clone(), "Invalid hashcode input.".to_string()))
+                _ => Err(LoxError::native_error(callee.clone(), "Invalid hashcode input."))
             }
         }
     }
-}
\ No newline at end of file
+
+    HelpFunction "help", 1 => {
+        fn call(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, _auto_clean : bool) -> RuntimeError<Option<Literal>> {
+            let text = match arguments[0].clone() {
+                Some(Literal::Function(f)) => {
+                    let signature = match f.as_any().downcast_ref::<LoxFunction>() {
+                        Some(v) => v.signature(),
+                        None => format!("{} (native, {} arguments)", f.get_name().lexeme, f.arity())
+                    };
+                    format!("fn {}\n{}", signature, indent_doc(f.doc(), "    "))
+                },
+                Some(Literal::Class(c)) => {
+                    let mut o = format!("class {}\n{}", c.name, indent_doc(c.doc.clone(), "    "));
+                    let mut names : Vec<&String> = c.methods.keys().collect();
+                    names.sort();
+                    for name in names {
+                        let method = &c.methods[name];
+                        o.push_str(&format!("  {}\n{}", method.signature(), indent_doc(method.doc(), "      ")));
+                    }
+                    o
+                },
+                _ => return Err(LoxError::type_mismatch(callee, "Expected function or class for help."))
+            };
+
+            print!("{}", text);
+            interpreter.stdout.push_str(&text);
+            Ok(Some(Literal::String(text)))
+        }
+    }
+}
@@ -17,24 +17,64 @@ pub enum TokenType {
     Decr, PlusEqual, MinusEqual,
     StarEqual, SlashEqual, 
     ModEqual, Hash, Lambda, 
-    Pipe, Question,
+    Pipe, Question, QuestionQuestion,
+    QuestionQuestionEqual, QuestionDot,
+    DotDot, DotDotEqual, DotDotDot, Arrow,
     
     //Literals
-    Identifier, String, Number, Bool,
+    Identifier, String, Number, Bool, Interpolation,
+
+    //comments kept for tooling
+    DocComment, Comment,
     
     //keywords
     And, Class, Else, ElseIf, False, Fn, For, If, Nil, Or, As, Is, In,
     Print, PrintLn, Return, Super, This, True, Var, While, Break, Continue,
+    Match, Enum, Interface, Impl, Abstract, Static, Throw, Try, Catch, Finally, Import,
     
     Eof
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub file : usize,
+    pub start : usize,
+    pub end : usize,
+    pub line : i32,
+    pub column : usize,
+}
+
+impl Span {
+    pub fn new(file : usize, start : usize, end : usize, line : i32, column : usize) -> Self {
+        Self {
+            file,
+            start,
+            end,
+            line,
+            column
+        }
+    }
+
+    //spans for tokens built outside the scanner only know their line
+    pub fn at_line(line : i32) -> Self {
+        Self {
+            line,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.column > 0
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub type_ : TokenType,
     pub lexeme : String,
     pub literal : Option<Literal>,
     pub line : i32,
+    pub span : Span,
 }
 
 impl Token {
@@ -43,7 +83,18 @@ impl Token {
             type_,
             lexeme : String::from(lexeme_),
             literal,
-            line
+            line,
+            span : Span::at_line(line)
+        }
+    }
+
+    pub fn new_with_span(type_ : TokenType, lexeme_ : &str, literal : Option<Literal>, span : Span) -> Self {
+        Self {
+            type_,
+            lexeme : String::from(lexeme_),
+            literal,
+            line : span.line,
+            span
         }
     }
 }
\ No newline at end of file
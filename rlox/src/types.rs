@@ -2,12 +2,13 @@ use std::collections::HashMap;
 use std::any::Any;
 
 use crate::tokens::*;
+use crate::errors::*;
 use crate::interpreter::*;
 use crate::environment::*;
 use crate::expressions::*;
 use crate::statements::*;
 
-pub type RuntimeError<T> = Result<T, (Token, String)>;
+pub type RuntimeError<T> = Result<T, LoxError>;
 
 #[derive(Clone, Debug)]
 pub enum LoopType {
@@ -20,30 +21,185 @@ pub enum LoopType {
 pub enum FunctionType {
     Normal,
     Method,
+    Static,
     Anon
 }
 
 #[derive(Clone, Debug)]
 pub struct Parameter {
     pub name : Token,
-    pub binding : Option<Token>
+    pub binding : Option<TypeAnnotation>,
+    //set when the argument is destructured, name is then a hidden slot for the whole value
+    pub pattern : Option<Pattern>,
+    //evaluated at call time inside the function, so it can use the parameters before it
+    pub default : Option<Box<dyn Expr>>,
+    //...rest collects every positional argument left over into a list
+    pub variadic : bool
 }
 
 impl Parameter {
-    pub fn new(name : Token, binding : Option<Token>) -> Self {
+    pub fn new(name : Token, binding : Option<TypeAnnotation>) -> Self {
         Self {
             name,
-            binding
+            binding,
+            pattern : None,
+            default : None,
+            variadic : false
         }
     }
 }
 
+//a type written in source, token is where it starts so errors can point at it
+#[derive(Clone, Debug)]
+pub struct TypeAnnotation {
+    pub token : Token,
+    pub kind : TypeKind,
+    //a trailing ? lets the value also be nil
+    pub nullable : bool
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeKind {
+    Num,
+    String,
+    Bool,
+    //list<T> checks every item, a bare list takes any
+    List(Option<Box<TypeAnnotation>>),
+    Map,
+    Fn,
+    //an instance of the class or anything inheriting from it
+    Class(String)
+}
+
+impl TypeAnnotation {
+    pub fn new(token : Token, kind : TypeKind, nullable : bool) -> Self {
+        Self {
+            token,
+            kind,
+            nullable
+        }
+    }
+
+    pub fn accepts(&self, value : &Option<Literal>) -> bool {
+        match (&self.kind, value) {
+            (_, None) => self.nullable,
+            (TypeKind::Num, Some(Literal::Number(_))) | (TypeKind::Num, Some(Literal::StrongNumber(_))) => true,
+            (TypeKind::String, Some(Literal::String(_))) | (TypeKind::String, Some(Literal::StrongString(_))) => true,
+            (TypeKind::Bool, Some(Literal::Boolean(_))) | (TypeKind::Bool, Some(Literal::StrongBoolean(_))) => true,
+            (TypeKind::List(item), Some(Literal::Collection(c))) | (TypeKind::List(item), Some(Literal::StrongCollection(c))) => {
+                match item {
+                    Some(t) => c.iter().all(|v| t.accepts(v)),
+                    None => true
+                }
+            },
+            (TypeKind::Map, Some(Literal::Map(_))) => true,
+            (TypeKind::Fn, Some(Literal::Function(_))) => true,
+            (TypeKind::Class(name), v) => Interpreter::is_instance_of(v, name),
+            _ => false
+        }
+    }
+}
+
+impl std::fmt::Display for TypeAnnotation {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            TypeKind::Num => write!(f, "num")?,
+            TypeKind::String => write!(f, "string")?,
+            TypeKind::Bool => write!(f, "bool")?,
+            TypeKind::List(Some(item)) => write!(f, "list<{}>", item)?,
+            TypeKind::List(None) => write!(f, "list")?,
+            TypeKind::Map => write!(f, "map")?,
+            TypeKind::Fn => write!(f, "fn")?,
+            TypeKind::Class(name) => write!(f, "{}", name)?
+        }
+        match self.nullable {
+            true => write!(f, "?"),
+            false => Ok(())
+        }
+    }
+}
+
+//how many positional arguments a callable takes, max is None when the last parameter is variadic
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Arity {
+    pub min : usize,
+    pub max : Option<usize>
+}
+
+impl Arity {
+    pub fn new(min : usize, max : Option<usize>) -> Self {
+        Self {
+            min,
+            max
+        }
+    }
+
+    pub fn exact(n : usize) -> Self {
+        Self::new(n, Some(n))
+    }
+
+    pub fn of(params : &[Parameter]) -> Self {
+        let min = params.iter().filter(|p| p.default.is_none() && !p.variadic).count();
+        match params.last() {
+            Some(p) if p.variadic => Self::new(min, None),
+            _ => Self::new(min, Some(params.len()))
+        }
+    }
+
+    pub fn accepts(&self, n : usize) -> bool {
+        n >= self.min && !matches!(self.max, Some(max) if n > max)
+    }
+
+    //every call other could take, this can take too
+    pub fn covers(&self, other : &Arity) -> bool {
+        self.min <= other.min && match (self.max, other.max) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(max), Some(other_max)) => max >= other_max
+        }
+    }
+
+    pub fn error(&self, at : &Token, got : usize) -> LoxError {
+        LoxError::arity(at.clone(), &format!("Expected {} arguments but got {}.", self, got))
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min)
+        }
+    }
+}
+
+//what a callable without named parameters does with a call, only a count check
+pub fn positional_arguments(name : &str, arity : Arity, at : &Token, arguments : Vec<Option<Literal>>, named : Vec<(Token, Option<Literal>)>) -> RuntimeError<Vec<Option<Literal>>> {
+    if let Some((argument, _)) = named.first() {
+        return Err(LoxError::arity(argument.clone(), &format!("'{}' does not take named arguments.", name)));
+    }
+    if !arity.accepts(arguments.len()) {
+        return Err(arity.error(at, arguments.len()));
+    }
+    Ok(arguments)
+}
+
 pub trait LoxCallable : std::fmt::Debug  {
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
     fn call(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, auto_clean : bool) -> RuntimeError<Option<Literal>>;
     fn clone_dyn(&self) -> Box<dyn LoxCallable>;
     fn get_name(&self) -> Token;
     fn as_any(&self) -> &dyn Any;
+
+    fn doc(&self) -> Option<String> {
+        None
+    }
+
+    //lines a call's positional and named arguments up with the parameters, giving back one value per parameter
+    fn arrange(&self, _interpreter : &mut Interpreter, at : &Token, arguments : Vec<Option<Literal>>, named : Vec<(Token, Option<Literal>)>) -> RuntimeError<Vec<Option<Literal>>> {
+        positional_arguments(&self.get_name().lexeme, self.arity(), at, arguments, named)
+    }
 }
 
 impl Clone for Box<dyn LoxCallable> {
@@ -64,6 +220,13 @@ pub trait StmtVisitor {
     fn visit_return_stmt(&mut self, stmt : &Return) -> RuntimeError<Option<Literal>>;
     fn visit_break_stmt(&mut self, stmt : &Break) -> RuntimeError<Option<Literal>>;
     fn visit_continue_stmt(&mut self, stmt : &Continue) -> RuntimeError<Option<Literal>>;
+    fn visit_match_stmt(&mut self, stmt : &Match) -> RuntimeError<Option<Literal>>;
+    fn visit_destructure_stmt(&mut self, stmt : &Destructure) -> RuntimeError<Option<Literal>>;
+    fn visit_enum_stmt(&mut self, stmt : &Enum) -> RuntimeError<Option<Literal>>;
+    fn visit_interface_stmt(&mut self, stmt : &Interface) -> RuntimeError<Option<Literal>>;
+    fn visit_throw_stmt(&mut self, stmt : &Throw) -> RuntimeError<Option<Literal>>;
+    fn visit_try_stmt(&mut self, stmt : &Try) -> RuntimeError<Option<Literal>>;
+    fn visit_import_stmt(&mut self, stmt : &Import) -> RuntimeError<Option<Literal>>;
 }
     
 pub trait ExprVisitor {
@@ -74,6 +237,7 @@ pub trait ExprVisitor {
     fn visit_literal_expr(&mut self, expr : &LiteralExp) -> RuntimeError<Option<Literal>>;
     fn visit_var_expr(&mut self, expr : &VarExpr) -> RuntimeError<Option<Literal>>;
     fn visit_assign_expr(&mut self, expr : &Assign) -> RuntimeError<Option<Literal>>;
+    fn visit_assign_pattern_expr(&mut self, expr : &AssignPattern) -> RuntimeError<Option<Literal>>;
     fn visit_logical_expr(&mut self, expr : &Logical) -> RuntimeError<Option<Literal>>;
     fn visit_call_expr(&mut self, expr : &Call) -> RuntimeError<Option<Literal>>;
     fn visit_get_expr(&mut self, expr : &Get) -> RuntimeError<Option<Literal>>;
@@ -82,7 +246,13 @@ pub trait ExprVisitor {
     fn visit_super_expr(&mut self, expr : &Super) -> RuntimeError<Option<Literal>>;
     fn visit_index_expr(&mut self, expr : &Index) -> RuntimeError<Option<Literal>>;
     fn visit_index_get_expr(&mut self, expr : &IndexGet) -> RuntimeError<Option<Literal>>;
+    fn visit_optional_chain_expr(&mut self, expr : &OptionalChain) -> RuntimeError<Option<Literal>>;
     fn visit_index_set_expr(&mut self, expr : &IndexSet, coll : Vec<Box<Option<Literal>>>) -> RuntimeError<Option<Literal>>;
+    fn visit_slice_expr(&mut self, expr : &Slice) -> RuntimeError<Option<Literal>>;
+    fn visit_map_expr(&mut self, expr : &MapLiteral) -> RuntimeError<Option<Literal>>;
+    fn visit_iterable_expr(&mut self, expr : &Iterable) -> RuntimeError<Option<Literal>>;
+    fn visit_range_expr(&mut self, expr : &RangeExpr) -> RuntimeError<Option<Literal>>;
+    fn visit_spread_expr(&mut self, expr : &Spread) -> RuntimeError<Option<Literal>>;
 }
 
 pub trait Stmt : std::fmt::Debug { 
@@ -123,7 +293,270 @@ pub enum Literal {
     Return(Box<Literal>),
     Keyword(String),
     Collection(Vec<Box<Option<Literal>>>),
-    StrongCollection(Vec<Box<Option<Literal>>>)
+    StrongCollection(Vec<Box<Option<Literal>>>),
+    Map(LoxMap),
+    Range(LoxRange),
+    Enum(Box<LoxEnum>),
+    Variant(Box<LoxVariant>),
+    Interface(Box<LoxInterface>),
+    Module(Box<LoxModule>)
+}
+
+#[derive(Clone, Debug)]
+pub struct LoxEnum {
+    pub name : String,
+    //in declaration order, each with the names of the values it carries
+    pub variants : Vec<(String, Vec<String>)>,
+    pub doc : Option<String>
+}
+
+impl LoxEnum {
+    pub fn new(name : String, variants : Vec<(String, Vec<String>)>, doc : Option<String>) -> Self {
+        Self {
+            name,
+            variants,
+            doc
+        }
+    }
+
+    //the variant with no values filled in yet
+    pub fn variant(&self, name : &str) -> Option<LoxVariant> {
+        self.variants.iter()
+            .find(|(n, _)| n == name)
+            .map(|(n, fields)| LoxVariant::new(self.name.clone(), n.clone(), fields.clone(), Vec::new()))
+    }
+
+    //only variants that carry nothing can be listed, the others need values to exist
+    pub fn values(&self) -> Vec<Box<Option<Literal>>> {
+        self.variants.iter()
+            .filter(|(_, fields)| fields.is_empty())
+            .map(|(n, _)| Box::new(Some(Literal::Variant(Box::new(LoxVariant::new(self.name.clone(), n.clone(), Vec::new(), Vec::new()))))))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LoxVariant {
+    pub enum_name : String,
+    pub name : String,
+    pub fields : Vec<String>,
+    pub values : Vec<Option<Literal>>
+}
+
+impl LoxVariant {
+    pub fn new(enum_name : String, name : String, fields : Vec<String>, values : Vec<Option<Literal>>) -> Self {
+        Self {
+            enum_name,
+            name,
+            fields,
+            values
+        }
+    }
+
+    pub fn get(&self, field : &str) -> Option<Option<Literal>> {
+        self.fields.iter().position(|f| f == field).map(|i| self.values[i].clone())
+    }
+}
+
+//what Shape.Circle gives back for a variant carrying values, calling it builds the variant
+#[derive(Clone, Debug)]
+pub struct VariantConstructor {
+    pub variant : LoxVariant
+}
+
+impl VariantConstructor {
+    pub fn new(variant : LoxVariant) -> Self {
+        Self {
+            variant
+        }
+    }
+}
+
+impl LoxCallable for VariantConstructor {
+    fn get_name(&self) -> Token {
+        Token::new(TokenType::Identifier, &format!("{}.{}", self.variant.enum_name, self.variant.name), None, 0)
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exact(self.variant.fields.len())
+    }
+
+    fn call(&self, _interpreter : &mut Interpreter, _callee : Token, arguments : Vec<Option<Literal>>, _auto_clean : bool) -> RuntimeError<Option<Literal>> {
+        let mut variant = self.variant.clone();
+        variant.values = arguments;
+        Ok(Some(Literal::Variant(Box::new(variant))))
+    }
+
+    fn clone_dyn(&self) -> Box<dyn LoxCallable> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+//Color.values()
+#[derive(Clone, Debug)]
+pub struct EnumValues {
+    pub enumeration : LoxEnum
+}
+
+impl EnumValues {
+    pub fn new(enumeration : LoxEnum) -> Self {
+        Self {
+            enumeration
+        }
+    }
+}
+
+impl LoxCallable for EnumValues {
+    fn get_name(&self) -> Token {
+        Token::new(TokenType::Identifier, &format!("{}.values", self.enumeration.name), None, 0)
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exact(0)
+    }
+
+    fn call(&self, _interpreter : &mut Interpreter, _callee : Token, _arguments : Vec<Option<Literal>>, _auto_clean : bool) -> RuntimeError<Option<Literal>> {
+        Ok(Some(Literal::Collection(self.enumeration.values())))
+    }
+
+    fn doc(&self) -> Option<String> {
+        Some(format!("Every variant of {} that carries no values, in declaration order.", self.enumeration.name))
+    }
+
+    fn clone_dyn(&self) -> Box<dyn LoxCallable> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+//numbers are worked out when asked for, so a range costs the same no matter how long it is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoxRange {
+    pub start : f64,
+    pub end : f64,
+    pub step : f64,
+    pub inclusive : bool
+}
+
+impl LoxRange {
+    pub fn new(start : f64, end : f64, step : f64, inclusive : bool) -> Self {
+        Self {
+            start,
+            end,
+            step,
+            inclusive
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let steps = (self.end - self.start) / self.step;
+        let count = match self.inclusive {
+            true => steps.floor() + 1.0,
+            false => steps.ceil()
+        };
+        count.max(0.0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn nth(&self, n : usize) -> f64 {
+        self.start + n as f64 * self.step
+    }
+
+    pub fn contains(&self, x : f64) -> bool {
+        let steps = (x - self.start) / self.step;
+        steps >= 0.0 && steps.fract() == 0.0 && (steps as usize) < self.len()
+    }
+
+    pub fn items(&self) -> Vec<Box<Option<Literal>>> {
+        (0..self.len()).map(|n| Box::new(Some(Literal::Number(self.nth(n))))).collect()
+    }
+}
+
+//the hashed form of a map key, instances hash through their class name and what their hash() method returns
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Number(u64),
+    String(String),
+    Bool(bool),
+    Instance(String, Box<MapKey>)
+}
+
+impl MapKey {
+    pub fn of(value : &Literal) -> Option<Self> {
+        match value {
+            //0.0 and -0.0 compare equal so they need to be the same key
+            Literal::Number(x) | Literal::StrongNumber(x) => Some(MapKey::Number(if *x == 0.0 { 0 } else { x.to_bits() })),
+            Literal::String(s) | Literal::StrongString(s) => Some(MapKey::String(s.clone())),
+            Literal::Boolean(b) | Literal::StrongBoolean(b) => Some(MapKey::Bool(*b)),
+            _ => None
+        }
+    }
+}
+
+//keeps entries in insertion order so printing and iterating a map is stable
+#[derive(Clone, Debug, Default)]
+pub struct LoxMap {
+    slots : HashMap<MapKey, usize>,
+    entries : Vec<(Literal, Option<Literal>)>
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self {
+            slots : HashMap::new(),
+            entries : Vec::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, key : &MapKey) -> bool {
+        self.slots.contains_key(key)
+    }
+
+    pub fn get(&self, key : &MapKey) -> Option<&Option<Literal>> {
+        self.slots.get(key).map(|i| &self.entries[*i].1)
+    }
+
+    pub fn insert(&mut self, key : MapKey, original : Literal, value : Option<Literal>) {
+        match self.slots.get(&key) {
+            Some(i) => self.entries[*i].1 = value,
+            None => {
+                self.slots.insert(key, self.entries.len());
+                self.entries.push((original, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key : &MapKey) -> Option<Option<Literal>> {
+        let i = self.slots.remove(key)?;
+        for slot in self.slots.values_mut() {
+            if *slot > i {
+                *slot -= 1;
+            }
+        }
+        Some(self.entries.remove(i).1)
+    }
+
+    pub fn entries(&self) -> &[(Literal, Option<Literal>)] {
+        &self.entries
+    }
 }
 
 impl TryFrom<Literal> for Vec<Literal> {
@@ -364,52 +797,92 @@ impl LoxFunction {
         
         LoxFunction::new(self.declaration.clone(), environment, FunctionType::Method, self.is_init)
     }
-}
 
-impl LoxCallable for LoxFunction {
-    fn get_name(&self) -> Token {
-        *self.declaration.name.clone()
+    pub fn signature(&self) -> String {
+        let params : Vec<String> = self.declaration.params.iter()
+            .map(|p| {
+                let mut o = match &p.binding {
+                    Some(b) => format!("{} : {}", p.name.lexeme, b),
+                    None => p.name.lexeme.clone()
+                };
+                if p.variadic {
+                    o.insert_str(0, "...");
+                }
+                if let Some(default) = &p.default {
+                    let shown = match default.as_any().downcast_ref::<LiteralExp>().map(|l| l.value.clone()) {
+                        Some(Some(Literal::Number(x))) => x.to_string(),
+                        Some(Some(Literal::String(s))) => format!("\"{}\"", s),
+                        Some(Some(Literal::Boolean(b))) => b.to_string(),
+                        Some(None) => "nil".to_string(),
+                        _ => "…".to_string()
+                    };
+                    o.push_str(&format!(" = {}", shown));
+                }
+                o
+            })
+            .collect();
+        match &self.declaration.returns {
+            Some(r) => format!("{}({}) -> {}", self.declaration.name.lexeme, params.join(", "), r),
+            None => format!("{}({})", self.declaration.name.lexeme, params.join(", "))
+        }
     }
 
-    fn arity(&self) -> usize {
-        self.declaration.params.len()
+    pub fn class_name(&self) -> Option<String> {
+        match self.closure.values.get("this") {
+            Some(Some(Literal::Instance(i))) => Some(i.class.name.clone()),
+            _ => None
+        }
     }
-    
-    fn call(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, auto_clean : bool) -> RuntimeError<Option<Literal>> {
+
+    fn invoke(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, auto_clean : bool) -> RuntimeError<Option<Literal>> {
         let mut environment = self.closure.clone();
         environment.enclosing = Some(Box::new(interpreter.environment.clone()));
 
         interpreter.environment = environment.clone();
         
         for i in 0..self.declaration.params.len() {
-            if let Some(binding) = self.declaration.params[i].binding.clone() {
-                match (binding.type_, arguments[i].clone()) {
-                    (TokenType::Number, Some(Literal::StrongNumber(_))) => {  }
-                    (TokenType::Number, Some(Literal::Number(_))) => { }
-                    (TokenType::String, Some(Literal::StrongString(_))) => { }
-                    (TokenType::String, Some(Literal::String(_))) => { }
-                    (TokenType::Bool, Some(Literal::StrongBoolean(_))) => { }
-                    (TokenType::Bool, Some(Literal::Boolean(_))) => { }
-                    _ => {
-                        return Err((
-                            callee.clone(), 
-                            format!(
-                                "Invalid arg for parameter '{}'. Expected type '{}'", 
-                                self.declaration.params[i].name.lexeme.clone(),
-                                binding.lexeme
-                            )
-                        ));
-                    }
+            let param = &self.declaration.params[i];
+            if let Some(binding) = &param.binding {
+                //a variadic parameter's type is the type of each item it collects
+                let accepted = match (param.variadic, &arguments[i]) {
+                    (true, Some(Literal::Collection(items))) => items.iter().all(|v| binding.accepts(v)),
+                    _ => binding.accepts(&arguments[i])
+                };
+                if !accepted {
+                    return Err(LoxError::type_mismatch(
+                        callee.clone(), 
+                        &format!(
+                            "Invalid arg for parameter '{}'. Expected type '{}'", 
+                            param.name.lexeme.clone(),
+                            binding
+                        )
+                    ));
                 }
             }
             
             let mut e = interpreter.environment.clone();
             e.define(interpreter,
-                self.declaration.params[i].name.lexeme.clone(), 
+                param.name.lexeme.clone(), 
                 arguments[i].clone()
             );
+            if let (false, Some(binding)) = (param.variadic, &param.binding) {
+                e.annotate(param.name.lexeme.clone(), binding.clone());
+            }
             interpreter.environment = e;
         }
+
+        for (param, argument) in self.declaration.params.iter().zip(arguments.iter()) {
+            if let Some(pattern) = &param.pattern {
+                let mut bindings = Vec::new();
+                interpreter.destructure(pattern, argument, &callee, &mut bindings)?;
+
+                for (name, v) in bindings {
+                    let mut e = interpreter.environment.clone();
+                    e.define(interpreter, name, v);
+                    interpreter.environment = e;
+                }
+            }
+        }
         
         let res = interpreter.execute_block(&self.declaration.body)?;
         
@@ -428,7 +901,7 @@ impl LoxCallable for LoxFunction {
                     Err(e)
                 },
                 _ => {
-                    Err((Token::new(TokenType::Identifier, "this", None, self.declaration.name.line), "Couldnt find this.".to_string()))
+                    Err(LoxError::undefined_variable(Token::new(TokenType::Identifier, "this", None, self.declaration.name.line), "Couldnt find this."))
                 }
             }
         }
@@ -439,13 +912,120 @@ impl LoxCallable for LoxFunction {
                 }
             }
             
-            if let Some(Literal::Return(v)) = res {
-                Ok(Some(*v))
+            let value = match res {
+                Some(Literal::Return(v)) => Some(*v),
+                _ => None
+            };
+            
+            if let Some(returns) = &self.declaration.returns {
+                if !returns.accepts(&value) {
+                    return Err(LoxError::type_mismatch(callee, &format!(
+                        "Function '{}' should return {} but returned {}.",
+                        self.declaration.name.lexeme,
+                        returns,
+                        Interpreter::type_name(&value)
+                    )));
+                }
             }
-            else {
-                Ok(None)
+            Ok(value)
+        }
+    }
+}
+
+impl LoxCallable for LoxFunction {
+    fn get_name(&self) -> Token {
+        *self.declaration.name.clone()
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::of(&self.declaration.params)
+    }
+
+    fn arrange(&self, interpreter : &mut Interpreter, at : &Token, arguments : Vec<Option<Literal>>, named : Vec<(Token, Option<Literal>)>) -> RuntimeError<Vec<Option<Literal>>> {
+        let params = &self.declaration.params;
+        let arity = self.arity();
+        if named.is_empty() && !arity.accepts(arguments.len()) {
+            return Err(arity.error(at, arguments.len()));
+        }
+
+        //positional arguments fill the parameters in order, anything past them belongs to the variadic one
+        let mut slots : Vec<Option<Option<Literal>>> = vec![None; params.len()];
+        let mut extra = Vec::new();
+        let mut positional = arguments.into_iter();
+        for (slot, param) in slots.iter_mut().zip(params.iter()) {
+            if !param.variadic {
+                *slot = positional.next();
             }
         }
+        extra.extend(positional.map(Box::new));
+        if !extra.is_empty() {
+            match params.iter().position(|p| p.variadic) {
+                Some(i) => slots[i] = Some(Some(Literal::Collection(extra))),
+                None => return Err(arity.error(at, params.len() + extra.len()))
+            }
+        }
+
+        for (name, value) in named {
+            match params.iter().position(|p| p.name.lexeme == name.lexeme) {
+                Some(i) if slots[i].is_some() => {
+                    return Err(LoxError::arity(name.clone(), &format!("Got more than one value for parameter '{}'.", name.lexeme)));
+                },
+                Some(i) => slots[i] = Some(value),
+                None => {
+                    return Err(LoxError::arity(name.clone(), &format!("'{}' has no parameter named '{}'.", self.declaration.name.lexeme, name.lexeme)));
+                }
+            }
+        }
+
+        if slots.iter().all(|s| s.is_some()) {
+            return Ok(slots.into_iter().flatten().collect());
+        }
+
+        //defaults are worked out in the function's own scope with the parameters before them already defined
+        let previous = interpreter.environment.clone();
+        let mut environment = self.closure.clone();
+        environment.enclosing = Some(Box::new(previous.clone()));
+        interpreter.environment = environment;
+
+        let mut values = Vec::new();
+        for (slot, param) in slots.into_iter().zip(params.iter()) {
+            let value = match (slot, &param.default) {
+                (Some(v), _) => Ok(v),
+                (None, Some(default)) => interpreter.evaluate(default),
+                (None, None) if param.variadic => Ok(Some(Literal::Collection(vec!()))),
+                (None, None) => Err(LoxError::arity(at.clone(), &format!("Missing argument for parameter '{}'.", param.name.lexeme)))
+            };
+            match value {
+                Ok(v) => {
+                    let mut e = interpreter.environment.clone();
+                    e.define(interpreter, param.name.lexeme.clone(), v.clone());
+                    interpreter.environment = e;
+                    values.push(v);
+                },
+                Err(e) => {
+                    interpreter.environment = previous;
+                    return Err(e);
+                }
+            }
+        }
+
+        interpreter.environment = previous;
+        Ok(values)
+    }
+    
+    fn call(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, auto_clean : bool) -> RuntimeError<Option<Literal>> {
+        //initializers run inside the frame LoxClass::call already pushed
+        if self.is_init {
+            return self.invoke(interpreter, callee, arguments, auto_clean);
+        }
+
+        interpreter.push_frame(CallFrame::new(self.get_name().lexeme, self.class_name(), callee.line));
+        let res = self.invoke(interpreter, callee, arguments, auto_clean);
+        interpreter.pop_frame(res)
+    }
+    
+    fn doc(&self) -> Option<String> {
+        self.declaration.doc.clone()
     }
     
     fn clone_dyn(&self) -> Box<dyn LoxCallable> {
@@ -461,18 +1041,119 @@ impl LoxCallable for LoxFunction {
 pub struct LoxClass {
     pub name : String,
     pub methods : HashMap<String, LoxFunction>,
-    pub super_class : Option<Box<LoxClass>>
+    pub super_class : Option<Box<LoxClass>>,
+    pub doc : Option<String>,
+    pub interfaces : Vec<LoxInterface>,
+    pub abstracts : Vec<String>,
+    pub static_methods : HashMap<String, LoxFunction>,
+    pub getters : HashMap<String, LoxFunction>,
+    pub setters : HashMap<String, LoxFunction>,
+    //where this class keeps its class-level fields in Interpreter::statics
+    pub statics : Option<usize>
 }
 
 impl LoxClass {
-    pub fn new(name : String, methods : HashMap<String, LoxFunction>, super_class : Option<Box<LoxClass>>) -> Self {
+    pub fn new(name : String, methods : HashMap<String, LoxFunction>, super_class : Option<Box<LoxClass>>, doc : Option<String>) -> Self {
         Self {
             name,
             methods,
-            super_class
+            super_class,
+            doc,
+            interfaces : Vec::new(),
+            abstracts : Vec::new(),
+            static_methods : HashMap::new(),
+            getters : HashMap::new(),
+            setters : HashMap::new(),
+            statics : None
         }
     }
-    
+
+    //the nearest class up the chain with name in the table pick gives back
+    fn lookup(&self, name : &str, pick : fn(&LoxClass) -> &HashMap<String, LoxFunction>) -> Option<LoxFunction> {
+        match pick(self).get(name) {
+            Some(f) => Some(f.clone()),
+            None => self.super_class.as_ref().and_then(|sc| sc.lookup(name, pick))
+        }
+    }
+
+    pub fn find_getter(&self, name : &str) -> Option<LoxFunction> {
+        self.lookup(name, |c| &c.getters)
+    }
+
+    pub fn find_setter(&self, name : &str) -> Option<LoxFunction> {
+        self.lookup(name, |c| &c.setters)
+    }
+
+    pub fn find_static_method(&self, name : &str) -> Option<LoxFunction> {
+        self.lookup(name, |c| &c.static_methods)
+    }
+
+    //the slot in Interpreter::statics of the nearest class declaring the field, subclasses share it with their parent
+    pub fn static_owner(&self, name : &str, interpreter : &Interpreter) -> Option<usize> {
+        match self.statics {
+            Some(i) if interpreter.statics[i].contains_key(name) => Some(i),
+            _ => self.super_class.as_ref().and_then(|sc| sc.static_owner(name, interpreter))
+        }
+    }
+
+    pub fn get_static(&self, name : Token, interpreter : &Interpreter) -> RuntimeError<Option<Literal>> {
+        if let Some(i) = self.static_owner(&name.lexeme, interpreter) {
+            let value = interpreter.statics[i][&name.lexeme].clone();
+            if let Some(Literal::Instance(inst)) = &value {
+                if let Some(address) = inst.address {
+                    if let Some(a) = interpreter.references.get(address) {
+                        return Ok(a.clone());
+                    }
+                }
+            }
+            Ok(value)
+        }
+        else if let Some(f) = self.find_static_method(&name.lexeme) {
+            Ok(Some(Literal::Function(Box::new(f))))
+        }
+        else {
+            Err(LoxError::undefined_property(name.clone(), &format!("Class '{}' has no static member '{}'.", self.name, name.lexeme)))
+        }
+    }
+
+    pub fn set_static(&self, name : Token, value : Option<Literal>, interpreter : &mut Interpreter) -> RuntimeError<()> {
+        match self.static_owner(&name.lexeme, interpreter) {
+            Some(i) => {
+                interpreter.statics[i].insert(name.lexeme.clone(), value);
+                Ok(())
+            },
+            None => Err(LoxError::undefined_property(name.clone(), &format!("Class '{}' has no static field '{}'.", self.name, name.lexeme)))
+        }
+    }
+
+    //true when the closest class declaring name leaves it without a body
+    pub fn is_abstract(&self, name : &str) -> bool {
+        if self.methods.contains_key(name) {
+            false
+        }
+        else if self.abstracts.iter().any(|a| a == name) {
+            true
+        }
+        else {
+            self.super_class.as_ref().is_some_and(|sc| sc.is_abstract(name))
+        }
+    }
+
+    //abstract methods nothing down to this class has implemented, the class can't be called while there are any
+    pub fn unimplemented(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut class = Some(self);
+        while let Some(c) = class {
+            for a in &c.abstracts {
+                if !names.contains(a) && self.is_abstract(a) {
+                    names.push(a.clone());
+                }
+            }
+            class = c.super_class.as_deref();
+        }
+        names
+    }
+
     pub fn find_method(&self, name : String) -> Option<LoxFunction> {
         if self.methods.contains_key(&name.clone()) {
             Some(self.methods[&name.clone()].clone())
@@ -493,17 +1174,48 @@ impl LoxCallable for LoxClass {
         Token::new(TokenType::Class, self.name.as_str(), None, 0)
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         let initializer = self.find_method(self.get_name().lexeme.clone());
         if let Some(v) = initializer {
             v.arity()
         }
         else {
-            0
+            Arity::exact(0)
+        }
+    }
+
+    fn arrange(&self, interpreter : &mut Interpreter, at : &Token, arguments : Vec<Option<Literal>>, named : Vec<(Token, Option<Literal>)>) -> RuntimeError<Vec<Option<Literal>>> {
+        match self.find_method(self.get_name().lexeme.clone()) {
+            Some(initializer) => initializer.arrange(interpreter, at, arguments, named),
+            None => positional_arguments(&self.name, Arity::exact(0), at, arguments, named)
         }
     }
     
     fn call(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>, _auto_clean : bool) -> RuntimeError<Option<Literal>> {
+        let missing = self.unimplemented();
+        if !missing.is_empty() {
+            return Err(LoxError::not_callable(callee, &format!("Cannot create an instance of abstract class '{}', '{}' has no body.", self.name, missing.join("', '"))));
+        }
+        interpreter.push_frame(CallFrame::new(self.name.clone(), Some(self.name.clone()), callee.line));
+        let res = self.instantiate(interpreter, callee, arguments);
+        interpreter.pop_frame(res)
+    }
+    
+    fn doc(&self) -> Option<String> {
+        self.doc.clone()
+    }
+    
+    fn clone_dyn(&self) -> Box<dyn LoxCallable> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl LoxClass {
+    fn instantiate(&self, interpreter : &mut Interpreter, callee : Token, arguments : Vec<Option<Literal>>) -> RuntimeError<Option<Literal>> {
         let mut instance = LoxInstance::new(Box::new(self.clone()));
         
         let initializer = self.find_method(self.get_name().lexeme.clone());
@@ -519,13 +1231,63 @@ impl LoxCallable for LoxClass {
         
         Ok(Some(Literal::Instance(Box::new(instance))))
     }
-    
-    fn clone_dyn(&self) -> Box<dyn LoxCallable> {
-        Box::new(self.clone())
+}
+
+//a named set of methods a class promises to have
+#[derive(Clone, Debug)]
+pub struct LoxInterface {
+    pub name : String,
+    pub methods : Vec<(String, Arity)>,
+    pub doc : Option<String>
+}
+
+impl LoxInterface {
+    pub fn new(name : String, methods : Vec<(String, Arity)>, doc : Option<String>) -> Self {
+        Self {
+            name,
+            methods,
+            doc
+        }
     }
-    
-    fn as_any(&self) -> &dyn Any {
-        self
+}
+
+//what an import gives back, the top-level names a file declared
+#[derive(Clone, Debug)]
+pub struct LoxModule {
+    pub name : String,
+    pub path : String,
+    //only the module's own names, its functions are stored without closures and get this scope when they're taken out
+    pub exports : Environment
+}
+
+impl LoxModule {
+    pub fn new(name : String, path : String, exports : Environment) -> Self {
+        Self {
+            name,
+            path,
+            exports
+        }
+    }
+
+    //instances are read through the heap like variables are, so changes made since the import show
+    pub fn get(&self, name : &Token, interpreter : &Interpreter) -> RuntimeError<Option<Literal>> {
+        match self.exports.values.get(&name.lexeme) {
+            Some(Some(Literal::Instance(i))) if i.address.is_some() => {
+                Ok(interpreter.references.get(i.address.unwrap()).cloned().flatten())
+            },
+            Some(Some(Literal::Function(f))) => {
+                match f.as_any().downcast_ref::<LoxFunction>() {
+                    Some(function) if matches!(function.f_type, FunctionType::Normal) => {
+                        let mut function = function.clone();
+                        function.closure = self.exports.clone();
+                        Ok(Some(Literal::Function(Box::new(function))))
+                    },
+                    _ => Ok(Some(Literal::Function(f.clone())))
+                }
+            },
+            Some(v) => Ok(v.clone()),
+            None => Err(LoxError::undefined_property(name.clone(), &format!("Module '{}' has no export '{}'.", self.name, name.lexeme)))
+        }
     }
 }
 
@@ -553,11 +1315,11 @@ impl LoxInstance {
                         Ok(a.clone())
                     }
                     else {
-                        Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+                        Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
                     }
                 }
                 else {
-                    Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+                    Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
                 }
             }
             else {
@@ -565,7 +1327,7 @@ impl LoxInstance {
             }
         }
         else {
-            Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+            Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
         }
     }
 
@@ -577,28 +1339,46 @@ impl LoxInstance {
                         Ok(a.clone())
                     }
                     else {
-                        Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+                        Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
                     }
                 }
                 else {
-                    Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+                    Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
                 }
             }
             else {
                 Ok(self.fields[&name.lexeme].clone())
             }
         }
+        else if let Some(mut getter) = self.class.find_getter(&name.lexeme) {
+            getter.bind(interpreter, self).call(interpreter, name, Vec::new(), true)
+        }
         else {
             if let Some(mut v) = self.class.find_method(name.lexeme.clone()) {
                 Ok(Some(Literal::Function(Box::new(v.bind(interpreter, self)))))
             }
+            else if self.class.static_owner(&name.lexeme, interpreter).is_some() {
+                self.class.get_static(name, interpreter)
+            }
             else {
-                Err((name.clone(), format!("Undefined property '{}'.", name.lexeme.clone())))
+                Err(LoxError::undefined_property(name.clone(), &format!("Undefined property '{}'.", name.lexeme.clone())))
             }
         }
     }
 
-    pub fn set(&mut self, name : Token, value : Option<Literal>, interpreter : &mut Interpreter) {
+    //a setter works on the copy of this it was bound to, so the instance is read back afterwards to keep what it changed
+    pub fn set(&mut self, name : Token, value : Option<Literal>, interpreter : &mut Interpreter) -> RuntimeError<()> {
+        if let Some(mut setter) = self.class.find_setter(&name.lexeme) {
+            setter.bind(interpreter, self).call(interpreter, name, vec![value], true)?;
+            if let Some(Some(Literal::Instance(i))) = self.address.and_then(|a| interpreter.references.get(a)) {
+                *self = *i.clone();
+            }
+            return Ok(());
+        }
+        if !self.fields.contains_key(&name.lexeme) && self.class.static_owner(&name.lexeme, interpreter).is_some() {
+            return self.class.set_static(name, value, interpreter);
+        }
+        
         if let Some(Literal::Instance(inst)) = value.clone() {
             if let Some(address) = inst.address {
                 interpreter.references[address] = value.clone();
@@ -606,5 +1386,6 @@ impl LoxInstance {
         }
         
         self.fields.insert(name.lexeme.clone(), value.clone());
+        Ok(())
     }
 }
\ No newline at end of file